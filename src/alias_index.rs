@@ -0,0 +1,217 @@
+use crate::database::Database;
+use crate::structures::{ListId, PERMISSION};
+
+/// A list name as autocomplete sees it.
+pub struct IndexedAlias {
+    pub name: String,
+    key: Vec<char>,
+    pub list_id: ListId,
+    pub ping_permission: PERMISSION,
+    pub join_permission: PERMISSION,
+    pub visible: bool,
+}
+
+/// Every list name of a guild, kept in memory so autocomplete can rank them on each keystroke
+/// without going through the database.
+pub struct AliasIndex {
+    /// The alias revision of the database this index was built from.
+    pub revision: u64,
+    aliases: Vec<IndexedAlias>,
+}
+
+impl AliasIndex {
+    pub fn new(
+        revision: u64,
+        aliases: Vec<(String, ListId, PERMISSION, PERMISSION, bool)>,
+    ) -> AliasIndex {
+        AliasIndex {
+            revision,
+            aliases: aliases
+                .into_iter()
+                .map(
+                    |(name, list_id, ping_permission, join_permission, visible)| IndexedAlias {
+                        key: Database::alias_key(&name).chars().collect(),
+                        name,
+                        list_id,
+                        ping_permission,
+                        join_permission,
+                        visible,
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    /// The names matching a filter that are accepted, best first: names starting with the
+    /// filter, then names containing it, then names a few typos away from it.
+    pub fn search(
+        &self,
+        filter: &str,
+        amount: usize,
+        accept: impl Fn(&IndexedAlias) -> bool,
+    ) -> Vec<&IndexedAlias> {
+        let filter: Vec<char> = Database::alias_key(filter).chars().collect();
+        // Short filters are a few typos away from nearly everything
+        let max_distance = if filter.len() < 3 {
+            0
+        } else {
+            (filter.len() / 3).max(1)
+        };
+
+        let mut ranked: Vec<((u8, usize), &IndexedAlias)> = self
+            .aliases
+            .iter()
+            .filter(|alias| accept(alias))
+            .filter_map(|alias| {
+                AliasIndex::rank(&alias.key, &filter, max_distance).map(|rank| (rank, alias))
+            })
+            .collect();
+        ranked.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then(a.name.cmp(&b.name)));
+        ranked
+            .into_iter()
+            .take(amount)
+            .map(|(_, alias)| alias)
+            .collect()
+    }
+
+    /// The tier of a match and its place within the tier, lower is better.
+    fn rank(key: &[char], filter: &[char], max_distance: usize) -> Option<(u8, usize)> {
+        if filter.is_empty() {
+            return Some((0, 0));
+        }
+        if key.starts_with(filter) {
+            return Some((0, key.len()));
+        }
+        if let Some(position) = key
+            .windows(filter.len())
+            .position(|window| window == filter)
+        {
+            return Some((1, position));
+        }
+        // Compare against the start of the name too, the filter is often only partly typed
+        let distance = edit_distance(key, filter)
+            .min(edit_distance(&key[..key.len().min(filter.len())], filter));
+        (distance <= max_distance).then_some((2, distance))
+    }
+}
+
+/// Levenshtein distance between two names.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(names: &[&str]) -> AliasIndex {
+        AliasIndex::new(
+            0,
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    (
+                        name.to_string(),
+                        i as ListId,
+                        PERMISSION::NEUTRAL,
+                        PERMISSION::NEUTRAL,
+                        true,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn search(index: &AliasIndex, filter: &str) -> Vec<String> {
+        index
+            .search(filter, 25, |_| true)
+            .into_iter()
+            .map(|alias| alias.name.clone())
+            .collect()
+    }
+
+    fn distance(a: &str, b: &str) -> usize {
+        edit_distance(
+            &a.chars().collect::<Vec<char>>(),
+            &b.chars().collect::<Vec<char>>(),
+        )
+    }
+
+    #[test]
+    fn prefixes_rank_before_substrings_before_typos() {
+        let index = index(&[
+            "video games",
+            "gamers",
+            "games night",
+            "board games",
+            "games",
+        ]);
+        assert_eq!(
+            search(&index, "games"),
+            [
+                "games",
+                "games night",
+                "board games",
+                "video games",
+                "gamers"
+            ]
+        );
+    }
+
+    #[test]
+    fn typos_are_matched_against_partly_typed_names() {
+        let index = index(&["minecraft server", "terraria"]);
+        assert_eq!(search(&index, "minceraft"), ["minecraft server"]);
+        assert_eq!(search(&index, "minceraft serevr"), ["minecraft server"]);
+    }
+
+    #[test]
+    fn short_filters_do_not_match_typos() {
+        let index = index(&["ab", "ac"]);
+        assert_eq!(search(&index, "ab"), ["ab"]);
+        assert_eq!(search(&index, "xy"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn filters_are_folded_like_keys() {
+        let index = index(&["Ｍｉｎｅｃｒａｆｔ", "ﬁshing", "Art"]);
+        assert_eq!(search(&index, "MINE"), ["Ｍｉｎｅｃｒａｆｔ"]);
+        assert_eq!(search(&index, "fish"), ["ﬁshing"]);
+        assert_eq!(search(&index, "  aRT "), ["Art"]);
+    }
+
+    #[test]
+    fn empty_filter_lists_accepted_names_alphabetically() {
+        let index = index(&["c", "a", "b"]);
+        assert_eq!(search(&index, ""), ["a", "b", "c"]);
+        let accepted: Vec<&str> = index
+            .search("", 1, |alias| alias.list_id != 1)
+            .into_iter()
+            .map(|alias| alias.name.as_str())
+            .collect();
+        assert_eq!(accepted, ["b"]);
+    }
+
+    #[test]
+    fn edit_distance_edge_cases() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("same", "same"), 0);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("café", "cafe"), 1);
+        assert_eq!(distance("日本語", "日本"), 1);
+        assert_eq!(distance("🎮 games", "🎲 games"), 1);
+    }
+}
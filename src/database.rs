@@ -1,33 +1,40 @@
 use crate::structures::{
-    JoinResult, ListId, PingList, ProposalStatus, LOGCONDITION, LOGTRIGGER, PERMISSION,
+    DeliveryMode, JoinResult, ListId, PingHistoryFilter, PingList, PingRecord, ProposalStatus,
+    ScheduledPing, LOGCONDITION, LOGTRIGGER, PERMISSION,
 };
 use rusqlite::{named_params, params, Connection, Error, OptionalExtension, Result};
 use serenity::model::id::*;
+use std::collections::{BTreeSet, HashSet};
+use unicode_normalization::UnicodeNormalization;
+
+const DATABASE_VERSION: u32 = 22;
+/// Seconds after which join requests nobody decided on are dropped.
+const JOIN_REQUEST_EXPIRY: u64 = 604800;
+/// Seconds a member has to wait before asking to join a list again after being denied.
+const JOIN_REQUEST_COOLDOWN: u64 = 86400;
 
 pub struct Database {
     db: Connection,
+    /// Goes up whenever list names or the permissions autocomplete filters on change.
+    alias_revision: u64,
 }
 
 impl Database {
     // ANCHOR Initialization
     pub fn new(database_path: &str) -> Database {
         let conn = Connection::open(database_path).expect("Invalid path or SQL open failure");
-        let mut database = Database { db: conn };
+        let mut database = Database {
+            db: conn,
+            alias_revision: 0,
+        };
 
         match database
             .db
-            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .query_row("PRAGMA user_version", [], |row| row.get::<usize, u32>(0))
         {
-            Ok(2) => println!("The database was loaded succesfully"),
-            Ok(1) => {
-                database
-                    .db
-                    .execute_batch(
-                        "PRAGMA user_version = 2; \n\
-                        ALTER TABLE proposals ADD channel_id INTEGER NOT NULL DEFAULT 0; \n\
-                        ALTER TABLE proposals ADD message_id INTEGER NOT NULL DEFAULT 0;",
-                    )
-                    .expect("Issue updating database");
+            Ok(DATABASE_VERSION) => println!("The database was loaded succesfully"),
+            Ok(v @ 1..DATABASE_VERSION) => {
+                database.upgrade_tables(v);
                 println!("Updating database")
             }
             Ok(0) => {
@@ -47,9 +54,324 @@ impl Database {
         database
     }
 
+    fn upgrade_tables(&mut self, version: u32) {
+        if version < 2 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE proposals ADD channel_id INTEGER NOT NULL DEFAULT 0; \n\
+                    ALTER TABLE proposals ADD message_id INTEGER NOT NULL DEFAULT 0;",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 3 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS last_pings ( \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        list_id             INTEGER NOT NULL DEFAULT 0, \
+                        timestamp           INTEGER NOT NULL, \
+                        UNIQUE(guild_id, list_id) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 4 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE guilds ADD departure_grace INTEGER DEFAULT 2592000 CHECK( departure_grace >= 0 );\n\
+                    CREATE TABLE IF NOT EXISTS departures ( \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        user_id             INTEGER NOT NULL, \
+                        timestamp           INTEGER NOT NULL, \
+                        UNIQUE(guild_id, user_id) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 5 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS ping_history ( \
+                        id                  INTEGER PRIMARY KEY ASC, \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        channel_id          INTEGER NOT NULL, \
+                        user_id             INTEGER NOT NULL, \
+                        member_count        INTEGER NOT NULL, \
+                        timestamp           INTEGER NOT NULL );\n\
+                    CREATE INDEX IF NOT EXISTS ping_history_guild ON ping_history(guild_id, timestamp);\n\
+                    CREATE TABLE IF NOT EXISTS ping_history_lists ( \
+                        ping_id             INTEGER NOT NULL REFERENCES ping_history(id), \
+                        list_id             INTEGER NOT NULL, \
+                        UNIQUE(ping_id, list_id) );\n\
+                    CREATE TABLE IF NOT EXISTS ping_history_messages ( \
+                        ping_id             INTEGER NOT NULL REFERENCES ping_history(id), \
+                        message_id          INTEGER NOT NULL );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 6 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS scheduled_pings ( \
+                        id                  INTEGER PRIMARY KEY ASC, \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        channel_id          INTEGER NOT NULL, \
+                        user_id             INTEGER NOT NULL, \
+                        queries             TEXT NOT NULL, \
+                        note                TEXT NOT NULL DEFAULT '', \
+                        next_time           INTEGER NOT NULL, \
+                        interval            INTEGER NOT NULL DEFAULT 0 CHECK( interval >= 0 ) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 7 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE scheduled_pings ADD queued INTEGER NOT NULL DEFAULT 0 CHECK( queued = 0 OR queued = 1 );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 8 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE lists ADD delivery_mode INTEGER NOT NULL DEFAULT 0 CHECK( delivery_mode >= 0 AND delivery_mode <= 2 ); \n\
+                    ALTER TABLE user_settings ADD dm_pings INTEGER DEFAULT 1 CHECK( dm_pings = 0 OR dm_pings = 1 );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 9 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE guilds ADD ping_quota INTEGER DEFAULT 0 CHECK( ping_quota >= 0 ); \n\
+                    ALTER TABLE guilds ADD quota_window INTEGER DEFAULT 86400 CHECK( quota_window > 0 ); \n\
+                    ALTER TABLE role_settings ADD ping_quota INTEGER DEFAULT -1 CHECK( ping_quota >= -1 ); \n\
+                    ALTER TABLE user_settings ADD ping_quota INTEGER DEFAULT -1 CHECK( ping_quota >= -1 );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 10 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE guilds ADD confirm_threshold INTEGER DEFAULT 0 CHECK( confirm_threshold >= 0 );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 11 {
+            self.db
+                .execute_batch("ALTER TABLE lists ADD role_id INTEGER DEFAULT NULL;")
+                .expect("Issue updating database");
+        }
+        if version < 12 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS list_managers ( \
+                        list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                        user_id             INTEGER NOT NULL, \
+                        UNIQUE(list_id, user_id) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 13 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE lists ADD require_approval INTEGER DEFAULT 0 CHECK( require_approval = 0 OR require_approval = 1 ); \n\
+                    ALTER TABLE guilds ADD approval_channel INTEGER DEFAULT NULL; \n\
+                    CREATE TABLE IF NOT EXISTS join_requests ( \
+                        id                  INTEGER PRIMARY KEY ASC, \
+                        list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                        user_id             INTEGER NOT NULL, \
+                        timestamp           INTEGER NOT NULL, \
+                        UNIQUE(list_id, user_id) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 14 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE memberships ADD expires_at INTEGER DEFAULT NULL; \n\
+                    ALTER TABLE memberships ADD duration INTEGER DEFAULT NULL; \n\
+                    ALTER TABLE join_requests ADD duration INTEGER DEFAULT NULL; \n\
+                    ALTER TABLE guilds ADD expiry_notice INTEGER DEFAULT 1 CHECK( expiry_notice = 0 OR expiry_notice = 1 ); \n\
+                    CREATE INDEX IF NOT EXISTS memberships_expiry ON memberships(expires_at);",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 15 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS categories ( \
+                        id                  INTEGER PRIMARY KEY ASC, \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        name                TEXT NOT NULL, \
+                        UNIQUE(guild_id, name) );\n\
+                    ALTER TABLE lists ADD category_id INTEGER DEFAULT NULL REFERENCES categories(id);",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 16 {
+            self.db
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS list_includes ( \
+                        parent_id           INTEGER NOT NULL REFERENCES lists(id), \
+                        child_id            INTEGER NOT NULL REFERENCES lists(id), \
+                        UNIQUE(parent_id, child_id) );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 17 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE lists ADD archived_at INTEGER DEFAULT NULL; \n\
+                    ALTER TABLE guilds ADD archive_retention INTEGER DEFAULT 2592000 CHECK( archive_retention >= 0 );",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 18 {
+            self.db
+                .execute_batch(
+                    "ALTER TABLE alias ADD guild_id INTEGER DEFAULT NULL; \n\
+                    ALTER TABLE alias ADD key TEXT NOT NULL DEFAULT ''; \n\
+                    UPDATE alias SET guild_id = (SELECT lists.guild_id FROM lists WHERE lists.id = alias.list_id);",
+                )
+                .expect("Issue updating database");
+            self.upgrade_alias_keys();
+            self.db
+                .execute_batch(
+                    "CREATE UNIQUE INDEX IF NOT EXISTS alias_key ON alias(guild_id, key);",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 19 {
+            self.db
+                .execute_batch(
+                    "CREATE VIRTUAL TABLE IF NOT EXISTS list_search USING fts5(names, description, tokenize = 'unicode61 remove_diacritics 2');\n\
+                    INSERT INTO list_search (rowid, names, description) \
+                        SELECT lists.id, (SELECT group_concat(alias.name, ' ') FROM alias WHERE alias.list_id = lists.id), lists.description FROM lists;",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 20 {
+            // Requests can now expire while their buttons are still around, so their ids must
+            // never be handed out again
+            self.db
+                .execute_batch(
+                    "CREATE TABLE join_requests_new ( \
+                        id                  INTEGER PRIMARY KEY AUTOINCREMENT, \
+                        list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                        user_id             INTEGER NOT NULL, \
+                        timestamp           INTEGER NOT NULL, \
+                        duration            INTEGER DEFAULT NULL, \
+                        denied_at           INTEGER DEFAULT NULL, \
+                        UNIQUE(list_id, user_id) );\n\
+                    INSERT INTO join_requests_new (id, list_id, user_id, timestamp, duration) \
+                        SELECT id, list_id, user_id, timestamp, duration FROM join_requests;\n\
+                    DROP TABLE join_requests;\n\
+                    ALTER TABLE join_requests_new RENAME TO join_requests;",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 21 {
+            // User settings used to be unique per user rather than per guild and user, so a
+            // member could only have settings in one guild
+            self.db
+                .execute_batch(
+                    "CREATE TABLE user_settings_new ( \
+                        id                  INTEGER PRIMARY KEY ASC, \
+                        guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                        user_id             INTEGER NOT NULL, \
+                        propose_permission  INTEGER DEFAULT 0 CHECK( propose_permission >= 0 AND propose_permission <= 2), \
+                        ping_permission     INTEGER DEFAULT 0 CHECK( ping_permission >= 0 AND ping_permission <= 2), \
+                        ignore_gbcooldown   INTEGER DEFAULT 0 CHECK( ignore_gbcooldown = 0 OR ignore_gbcooldown = 1 ), \
+                        dm_pings            INTEGER DEFAULT 1 CHECK( dm_pings = 0 OR dm_pings = 1 ), \
+                        ping_quota          INTEGER DEFAULT -1 CHECK( ping_quota >= -1 ), \
+                        UNIQUE(guild_id, user_id) );\n\
+                    INSERT INTO user_settings_new (id, guild_id, user_id, propose_permission, ping_permission, ignore_gbcooldown, dm_pings, ping_quota) \
+                        SELECT id, guild_id, user_id, propose_permission, ping_permission, ignore_gbcooldown, dm_pings, ping_quota FROM user_settings;\n\
+                    DROP TABLE user_settings;\n\
+                    ALTER TABLE user_settings_new RENAME TO user_settings;",
+                )
+                .expect("Issue updating database");
+        }
+        if version < 22 {
+            self.db
+                .execute_batch("ALTER TABLE scheduled_pings ADD schedule TEXT DEFAULT NULL;")
+                .expect("Issue updating database");
+        }
+        self.db
+            .pragma_update(None, "user_version", DATABASE_VERSION)
+            .expect("Issue updating database");
+    }
+
+    /// Fills in the lookup keys of existing aliases. Aliases that collide with an older alias in
+    /// the same guild are dropped when both belong to the same list and renamed otherwise.
+    fn upgrade_alias_keys(&mut self) {
+        let aliases = {
+            let mut stmt = self
+                .db
+                .prepare("SELECT id, guild_id, list_id, name FROM alias ORDER BY id ASC")
+                .expect("Issue updating database");
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<usize, u64>(0)?,
+                        row.get::<usize, Option<u64>>(1)?,
+                        row.get::<usize, u64>(2)?,
+                        row.get::<usize, String>(3)?,
+                    ))
+                })
+                .expect("Issue updating database");
+            rows.collect::<Result<Vec<_>, _>>()
+                .expect("Issue updating database")
+        };
+
+        let mut taken: HashSet<(Option<u64>, String)> = HashSet::new();
+        let mut owners: Vec<(Option<u64>, String, ListId)> = vec![];
+        for (id, guild_id, list_id, name) in aliases {
+            let key = Database::alias_key(&name);
+            if !taken.contains(&(guild_id, key.clone())) {
+                taken.insert((guild_id, key.clone()));
+                owners.push((guild_id, key.clone(), list_id));
+                self.db
+                    .execute("UPDATE alias SET key = ?1 WHERE id = ?2", params![key, id])
+                    .expect("Issue updating database");
+                continue;
+            }
+            let same_list = owners
+                .iter()
+                .any(|owner| owner.0 == guild_id && owner.1 == key && owner.2 == list_id);
+            if same_list {
+                println!(
+                    "Alias {} of list {} duplicates another alias of the list, it was removed",
+                    name, list_id
+                );
+                self.db
+                    .execute("DELETE FROM alias WHERE id = ?1", params![id])
+                    .expect("Issue updating database");
+                continue;
+            }
+            let mut new_name = format!("{}-{}", name, list_id);
+            while taken.contains(&(guild_id, Database::alias_key(&new_name))) {
+                new_name.push('_');
+            }
+            let new_key = Database::alias_key(&new_name);
+            println!(
+                "Alias {} of list {} is also used by another list in guild {}, it was renamed to {}",
+                name,
+                list_id,
+                guild_id.unwrap_or(0),
+                new_name
+            );
+            taken.insert((guild_id, new_key.clone()));
+            owners.push((guild_id, new_key.clone(), list_id));
+            self.db
+                .execute(
+                    "UPDATE alias SET name = ?1, key = ?2 WHERE id = ?3",
+                    params![new_name, new_key, id],
+                )
+                .expect("Issue updating database");
+        }
+    }
+
     fn init_tables(&mut self) -> () {
-        let statement = "PRAGMA user_version = 2; \n\
-            PRAGMA foreign_keys = ON;\n\
+        let statement = "PRAGMA foreign_keys = ON;\n\
             CREATE TABLE IF NOT EXISTS guilds ( \
                 id                  INTEGER PRIMARY KEY NOT NULL, \
                 general_canping     INTEGER DEFAULT 1 CHECK( general_canping = 0 OR general_canping = 1 ) , \
@@ -58,17 +380,30 @@ impl Database {
                 general_propose     INTEGER DEFAULT 1 CHECK( general_propose = 0 OR general_propose = 1 ), \
                 propose_threshold   INTEGER DEFAULT 8 CHECK( propose_threshold > 0 ), \
                 propose_timeout     INTEGER DEFAULT 86400 CHECK( propose_timeout > 2 ), \
-                log_channel         INTEGER DEFAULT 0 );\n\
+                log_channel         INTEGER DEFAULT 0, \
+                departure_grace     INTEGER DEFAULT 2592000 CHECK( departure_grace >= 0 ), \
+                ping_quota          INTEGER DEFAULT 0 CHECK( ping_quota >= 0 ), \
+                quota_window        INTEGER DEFAULT 86400 CHECK( quota_window > 0 ), \
+                confirm_threshold   INTEGER DEFAULT 0 CHECK( confirm_threshold >= 0 ), \
+                approval_channel    INTEGER DEFAULT NULL, \
+                expiry_notice       INTEGER DEFAULT 1 CHECK( expiry_notice = 0 OR expiry_notice = 1 ), \
+                archive_retention   INTEGER DEFAULT 2592000 CHECK( archive_retention >= 0 ) );\n\
             CREATE TABLE IF NOT EXISTS alias ( \
                 id                  INTEGER PRIMARY KEY ASC, \
                 list_id             INTEGER REFERENCES lists(id), \
                 name                TEXT NOT NULL, \
+                guild_id            INTEGER DEFAULT NULL, \
+                key                 TEXT NOT NULL DEFAULT '', \
                 UNIQUE(list_id, name) );\n\
+            CREATE UNIQUE INDEX IF NOT EXISTS alias_key ON alias(guild_id, key);\n\
             CREATE TABLE IF NOT EXISTS memberships ( \
                 id                  INTEGER PRIMARY KEY ASC, \
                 user_id             INTEGER NOT NULL, \
                 list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                expires_at          INTEGER DEFAULT NULL, \
+                duration            INTEGER DEFAULT NULL, \
                 UNIQUE(user_id, list_id) );\n\
+            CREATE INDEX IF NOT EXISTS memberships_expiry ON memberships(expires_at);\n\
             CREATE TABLE IF NOT EXISTS lists ( \
                 id                  INTEGER PRIMARY KEY ASC, \
                 guild_id            INTEGER REFERENCES guilds(id), \
@@ -76,21 +411,35 @@ impl Database {
                 cooldown            INTEGER DEFAULT -1 CHECK( cooldown >= -1 ), \
                 join_permission     INTEGER DEFAULT 0 CHECK( join_permission >= 0 AND join_permission <= 2 ), \
                 ping_permission     INTEGER DEFAULT 0 CHECK( ping_permission >= 0 AND ping_permission <= 2 ), \
-                visible             INTEGER DEFAULT 1 CHECK( visible = 0 OR visible = 1));\n\
+                visible             INTEGER DEFAULT 1 CHECK( visible = 0 OR visible = 1), \
+                delivery_mode       INTEGER NOT NULL DEFAULT 0 CHECK( delivery_mode >= 0 AND delivery_mode <= 2 ), \
+                role_id             INTEGER DEFAULT NULL, \
+                require_approval    INTEGER DEFAULT 0 CHECK( require_approval = 0 OR require_approval = 1 ), \
+                category_id         INTEGER DEFAULT NULL REFERENCES categories(id), \
+                archived_at         INTEGER DEFAULT NULL);\n\
+            CREATE TABLE IF NOT EXISTS categories ( \
+                id                  INTEGER PRIMARY KEY ASC, \
+                guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                name                TEXT NOT NULL, \
+                UNIQUE(guild_id, name) );\n\
             CREATE TABLE IF NOT EXISTS role_settings ( \
                 id                  INTEGER PRIMARY KEY ASC, \
                 guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
                 role_id             INTEGER UNIQUE NOT NULL, \
                 propose_permission  INTEGER DEFAULT 0 CHECK( propose_permission >= 0 AND propose_permission <= 2), \
                 ping_permission     INTEGER DEFAULT 0 CHECK( ping_permission >= 0 AND ping_permission <= 2), \
-                ignore_gbcooldown   INTEGER DEFAULT 0 CHECK( ignore_gbcooldown = 0 OR ignore_gbcooldown = 1 ) );\n\
+                ignore_gbcooldown   INTEGER DEFAULT 0 CHECK( ignore_gbcooldown = 0 OR ignore_gbcooldown = 1 ), \
+                ping_quota          INTEGER DEFAULT -1 CHECK( ping_quota >= -1 ) );\n\
             CREATE TABLE IF NOT EXISTS user_settings ( \
                 id                  INTEGER PRIMARY KEY ASC, \
                 guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
-                user_id             INTEGER UNIQUE NOT NULL, \
+                user_id             INTEGER NOT NULL, \
                 propose_permission  INTEGER DEFAULT 0 CHECK( propose_permission >= 0 AND propose_permission <= 2), \
                 ping_permission     INTEGER DEFAULT 0 CHECK( ping_permission >= 0 AND ping_permission <= 2), \
-                ignore_gbcooldown   INTEGER DEFAULT 0 CHECK( ignore_gbcooldown = 0 OR ignore_gbcooldown = 1 ) );\n\
+                ignore_gbcooldown   INTEGER DEFAULT 0 CHECK( ignore_gbcooldown = 0 OR ignore_gbcooldown = 1 ), \
+                dm_pings            INTEGER DEFAULT 1 CHECK( dm_pings = 0 OR dm_pings = 1 ), \
+                ping_quota          INTEGER DEFAULT -1 CHECK( ping_quota >= -1 ), \
+                UNIQUE(guild_id, user_id) );\n\
             CREATE TABLE IF NOT EXISTS channel_settings ( \
                 channel_id          INTEGER PRIMARY KEY, \
                 public_commands     INTEGER DEFAULT 0, \
@@ -113,8 +462,64 @@ impl Database {
                 list_id             INTEGER PRIMARY KEY REFERENCES lists(id), \
                 timestamp           INTEGER NOT NULL, \
                 channel_id          INTEGER NOT NULL DEFAULT 0, \
-                message_id          INTEGER NOT NULL DEFAULT 0 );";
-        self.db.execute_batch(statement).expect("Malformed SQL")
+                message_id          INTEGER NOT NULL DEFAULT 0 );\n\
+            CREATE TABLE IF NOT EXISTS last_pings ( \
+                guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                list_id             INTEGER NOT NULL DEFAULT 0, \
+                timestamp           INTEGER NOT NULL, \
+                UNIQUE(guild_id, list_id) );\n\
+            CREATE TABLE IF NOT EXISTS departures ( \
+                guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                user_id             INTEGER NOT NULL, \
+                timestamp           INTEGER NOT NULL, \
+                UNIQUE(guild_id, user_id) );\n\
+            CREATE TABLE IF NOT EXISTS ping_history ( \
+                id                  INTEGER PRIMARY KEY ASC, \
+                guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                channel_id          INTEGER NOT NULL, \
+                user_id             INTEGER NOT NULL, \
+                member_count        INTEGER NOT NULL, \
+                timestamp           INTEGER NOT NULL );\n\
+            CREATE INDEX IF NOT EXISTS ping_history_guild ON ping_history(guild_id, timestamp);\n\
+            CREATE TABLE IF NOT EXISTS ping_history_lists ( \
+                ping_id             INTEGER NOT NULL REFERENCES ping_history(id), \
+                list_id             INTEGER NOT NULL, \
+                UNIQUE(ping_id, list_id) );\n\
+            CREATE TABLE IF NOT EXISTS ping_history_messages ( \
+                ping_id             INTEGER NOT NULL REFERENCES ping_history(id), \
+                message_id          INTEGER NOT NULL );\n\
+            CREATE TABLE IF NOT EXISTS scheduled_pings ( \
+                id                  INTEGER PRIMARY KEY ASC, \
+                guild_id            INTEGER NOT NULL REFERENCES guilds(id), \
+                channel_id          INTEGER NOT NULL, \
+                user_id             INTEGER NOT NULL, \
+                queries             TEXT NOT NULL, \
+                note                TEXT NOT NULL DEFAULT '', \
+                next_time           INTEGER NOT NULL, \
+                interval            INTEGER NOT NULL DEFAULT 0 CHECK( interval >= 0 ), \
+                queued              INTEGER NOT NULL DEFAULT 0 CHECK( queued = 0 OR queued = 1 ), \
+                schedule            TEXT DEFAULT NULL );\n\
+            CREATE TABLE IF NOT EXISTS list_managers ( \
+                list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                user_id             INTEGER NOT NULL, \
+                UNIQUE(list_id, user_id) );\n\
+            CREATE TABLE IF NOT EXISTS join_requests ( \
+                id                  INTEGER PRIMARY KEY AUTOINCREMENT, \
+                list_id             INTEGER NOT NULL REFERENCES lists(id), \
+                user_id             INTEGER NOT NULL, \
+                timestamp           INTEGER NOT NULL, \
+                duration            INTEGER DEFAULT NULL, \
+                denied_at           INTEGER DEFAULT NULL, \
+                UNIQUE(list_id, user_id) );\n\
+            CREATE TABLE IF NOT EXISTS list_includes ( \
+                parent_id           INTEGER NOT NULL REFERENCES lists(id), \
+                child_id            INTEGER NOT NULL REFERENCES lists(id), \
+                UNIQUE(parent_id, child_id) );\n\
+            CREATE VIRTUAL TABLE IF NOT EXISTS list_search USING fts5(names, description, tokenize = 'unicode61 remove_diacritics 2');";
+        self.db.execute_batch(statement).expect("Malformed SQL");
+        self.db
+            .pragma_update(None, "user_version", DATABASE_VERSION)
+            .expect("Malformed SQL")
     }
 
     //ANCHOR Guild setup
@@ -181,159 +586,1033 @@ impl Database {
         Ok(())
     }
 
-    //ANCHOR List functions
-    pub fn add_list(&mut self, guild_id: GuildId, name: &str) -> Option<ListId> {
-        let tx = self.db.transaction().unwrap();
-        let suc = tx
-            .execute(
-                "INSERT INTO lists (guild_id) VALUES (?1);",
+    pub fn set_departure_grace(&mut self, guild_id: GuildId, value: u64) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET departure_grace = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_departure_grace(&self, guild_id: GuildId) -> u64 {
+        self.db
+            .query_row(
+                "SELECT departure_grace FROM guilds WHERE id = ?1",
                 params![guild_id.get()],
+                |row| row.get::<usize, u64>(0),
             )
-            .unwrap();
-        if suc == 0 {
-            tx.finish().unwrap();
-            return None;
-        }
-        let list_id = tx.last_insert_rowid() as u64;
-        Database::add_alias(&tx, list_id, name);
-        tx.commit().unwrap();
-        Some(list_id)
+            .unwrap()
     }
 
-    pub fn remove_list(&mut self, list_id: ListId) -> Result<bool, Error> {
-        self.remove_all_alias(list_id)?;
-        self.remove_all_members(list_id)?;
-        self.remove_proposal(list_id)?;
-        Ok(self
-            .db
-            .execute("DELETE FROM lists WHERE id = ?1", params![list_id])?
-            > 0)
+    pub fn set_confirm_threshold(&mut self, guild_id: GuildId, value: u64) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET confirm_threshold = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
+        )?;
+        Ok(())
     }
 
-    //List config
-    pub fn set_pingable(&mut self, list_id: ListId, pingable: PERMISSION) -> bool {
+    /// Pings reaching more members than this need to be confirmed first, 0 disables this.
+    pub fn get_confirm_threshold(&self, guild_id: GuildId) -> u64 {
         self.db
-            .execute(
-                "UPDATE lists SET ping_permission = ?1 WHERE id = ?2",
-                params![pingable as u64, list_id],
+            .query_row(
+                "SELECT confirm_threshold FROM guilds WHERE id = ?1",
+                params![guild_id.get()],
+                |row| row.get::<usize, u64>(0),
             )
             .unwrap()
-            > 0
     }
 
-    pub fn set_joinable(&mut self, list_id: ListId, joinable: PERMISSION) -> bool {
-        self.db
-            .execute(
-                "UPDATE lists SET join_permission = ?1 WHERE id = ?2",
-                params![joinable as u64, list_id],
-            )
-            .unwrap()
-            > 0
+    pub fn set_approval_channel(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET approval_channel = ?1 WHERE id = ?2",
+            params![channel_id.map(|c| c.get()), guild_id.get()],
+        )?;
+        Ok(())
     }
 
-    pub fn set_visible(&mut self, list_id: ListId, visible: bool) -> bool {
+    pub fn set_expiry_notice(&mut self, guild_id: GuildId, value: bool) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET expiry_notice = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
+        )?;
+        Ok(())
+    }
+
+    /// Whether members are told by direct message when a temporary membership ends.
+    pub fn get_expiry_notice(&self, guild_id: GuildId) -> bool {
         self.db
-            .execute(
-                "UPDATE lists SET visible = ?1 WHERE id = ?2",
-                params![visible, list_id],
+            .query_row(
+                "SELECT expiry_notice FROM guilds WHERE id = ?1",
+                params![guild_id.get()],
+                |row| row.get::<usize, bool>(0),
             )
             .unwrap()
-            > 0
     }
 
-    pub fn set_description(&mut self, list_id: ListId, value: &str) -> bool {
+    pub fn set_archive_retention(&mut self, guild_id: GuildId, value: u64) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET archive_retention = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
+        )?;
+        Ok(())
+    }
+
+    /// How long archived lists are kept before they are deleted for good.
+    pub fn get_archive_retention(&self, guild_id: GuildId) -> u64 {
         self.db
-            .execute(
-                "UPDATE lists SET description = ?1 WHERE id = ?2",
-                params![value, list_id],
+            .query_row(
+                "SELECT archive_retention FROM guilds WHERE id = ?1",
+                params![guild_id.get()],
+                |row| row.get::<usize, u64>(0),
             )
             .unwrap()
-            > 0
     }
-    pub fn set_cooldown(&mut self, list_id: ListId, value: i64) -> bool {
+
+    /// Where join requests are posted, without one they are sent to the list managers.
+    pub fn get_approval_channel(&self, guild_id: GuildId) -> Option<ChannelId> {
         self.db
-            .execute(
-                "UPDATE lists SET cooldown = ?1 WHERE id = ?2",
-                params![value, list_id],
+            .query_row(
+                "SELECT approval_channel FROM guilds WHERE id = ?1",
+                params![guild_id.get()],
+                |row| row.get::<usize, Option<u64>>(0),
             )
             .unwrap()
-            > 0
-    }
-
-    pub fn add_alias_inline(&self, list_id: ListId, name: &str) -> bool {
-        Database::add_alias(&self.db, list_id, name)
+            .map(ChannelId::new)
     }
 
-    pub fn add_alias(db: &Connection, list_id: ListId, name: &str) -> bool {
-        match db.execute(
-            "INSERT INTO alias (list_id, name) VALUES (?1, ?2)",
-            params![list_id, name],
-        ) {
-            Err(Error::SqliteFailure(
-                rusqlite::ffi::Error {
-                    code: _,
-                    extended_code: 2067,
-                },
-                _,
-            )) => false, // Unique constraint violation, alias already exists for this list
-            Ok(_) => true,
-            Err(a) => Err(a).unwrap(),
-        }
-    }
+    // Quotas, a quota of 0 means unlimited and -1 on roles and users falls back to the guild.
 
-    pub fn remove_alias(
-        &mut self,
-        db: Option<&Connection>,
-        list_id: ListId,
-        name: &str,
-    ) -> Result<(), Error> {
-        let db = db.unwrap_or(&self.db);
-        db.execute(
-            "DELETE FROM alias WHERE list_id = ?1 AND name = ?2",
-            params![list_id, name],
+    pub fn set_guild_quota(&mut self, guild_id: GuildId, value: u64) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE guilds SET ping_quota = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
         )?;
         Ok(())
     }
 
-    fn remove_all_alias(&mut self, list_id: ListId) -> Result<(), Error> {
-        self.db
-            .execute("DELETE FROM alias WHERE list_id = ?1", params![list_id])?;
-        Ok(())
-    }
-
-    fn remove_all_members(&mut self, list_id: ListId) -> Result<(), Error> {
+    pub fn set_guild_quota_window(&mut self, guild_id: GuildId, value: u64) -> Result<(), Error> {
         self.db.execute(
-            "DELETE FROM memberships WHERE list_id = ?1",
-            params![list_id],
+            "UPDATE guilds SET quota_window = ?1 WHERE id = ?2",
+            params![value, guild_id.get()],
         )?;
         Ok(())
     }
 
-    //Getters
-
-    pub fn get_list_permissions(&self, list_id: ListId) -> (i64, PERMISSION, PERMISSION) {
+    /// The default amount of pings per member and the window they are counted over.
+    pub fn get_guild_quota(&self, guild_id: GuildId) -> (u64, u64) {
         self.db
             .query_row(
-                "SELECT cooldown, join_permission, ping_permission FROM lists WHERE id=?1",
-                params![list_id],
-                |row| {
-                    Ok((
-                        row.get::<usize, i64>(0)?,
-                        PERMISSION::fromint(row.get::<usize, u64>(1)?),
-                        PERMISSION::fromint(row.get::<usize, u64>(2)?),
-                    ))
-                },
+                "SELECT ping_quota, quota_window FROM guilds WHERE id = ?1",
+                params![guild_id.get()],
+                |row| Ok((row.get::<usize, u64>(0)?, row.get::<usize, u64>(1)?)),
             )
             .unwrap()
     }
 
-    pub fn get_list_id_by_name(&mut self, list_name: &str, guild_id: GuildId) -> Option<ListId> {
-        self.db.query_row(
-                "SELECT lists.id FROM lists, alias WHERE alias.name=?1 AND alias.list_id = lists.id AND lists.guild_id=?2",
-                params![list_name, guild_id.get()], |row| row.get::<usize, u64>(0)
-            ).optional().unwrap()
-    }
-
+    /// The amount of pings a user sent since the given time, and when the oldest of those was.
+    pub fn get_user_pings_since(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        since: u64,
+    ) -> (usize, u64) {
+        self.db
+            .query_row(
+                "SELECT COUNT(*), IFNULL(MIN(timestamp), 0) FROM ping_history \
+                WHERE guild_id = ?1 AND user_id = ?2 AND timestamp > ?3",
+                params![guild_id.get(), user_id.get(), since],
+                |row| Ok((row.get::<usize, usize>(0)?, row.get::<usize, u64>(1)?)),
+            )
+            .unwrap()
+    }
+
+    // Departures, memberships of members that left are kept for the grace period.
+
+    pub fn add_departure(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        timestamp: u64,
+    ) -> Result<(), Error> {
+        self.db.execute(
+            "INSERT OR IGNORE INTO departures (guild_id, user_id, timestamp) VALUES (?1, ?2, ?3)",
+            params![guild_id.get(), user_id.get(), timestamp],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_departure(&mut self, guild_id: GuildId, user_id: UserId) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "DELETE FROM departures WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id.get(), user_id.get()],
+        )? > 0)
+    }
+
+    /// Records a departure for everyone on a list of the guild who is not among its members,
+    /// catching those who left while the bot was not running. Returns the amount recorded.
+    pub fn reconcile_departures(
+        &mut self,
+        guild_id: GuildId,
+        present: &BTreeSet<UserId>,
+        timestamp: u64,
+    ) -> Result<usize, Error> {
+        let tx = self.db.transaction()?;
+        let mut recorded = 0;
+        {
+            let mut stmt = tx.prepare(
+                "SELECT DISTINCT memberships.user_id FROM memberships \
+                JOIN lists ON lists.id = memberships.list_id \
+                WHERE lists.guild_id = ?1",
+            )?;
+            let absent: Vec<u64> = stmt
+                .query_map(params![guild_id.get()], |row| row.get::<usize, u64>(0))?
+                .collect::<Result<Vec<u64>>>()?
+                .into_iter()
+                .filter(|user_id| !present.contains(&UserId::new(*user_id)))
+                .collect();
+            for user_id in absent {
+                recorded += tx.execute(
+                    "INSERT OR IGNORE INTO departures (guild_id, user_id, timestamp) \
+                    VALUES (?1, ?2, ?3)",
+                    params![guild_id.get(), user_id, timestamp],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(recorded)
+    }
+
+    /// Removes all memberships of members whose grace period ran out, returns the amount removed.
+    pub fn prune_departed_members(&mut self, now: u64) -> Result<usize, Error> {
+        let tx = self.db.transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM memberships WHERE EXISTS ( \
+                SELECT 1 FROM departures, guilds, lists \
+                WHERE lists.id = memberships.list_id \
+                AND departures.user_id = memberships.user_id \
+                AND departures.guild_id = lists.guild_id \
+                AND guilds.id = departures.guild_id \
+                AND departures.timestamp + guilds.departure_grace <= ?1)",
+            params![now],
+        )?;
+        tx.execute(
+            "DELETE FROM departures WHERE departures.timestamp + ( \
+                SELECT guilds.departure_grace FROM guilds WHERE guilds.id = departures.guild_id \
+            ) <= ?1",
+            params![now],
+        )?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    // Cooldowns, a list id of 0 holds the guild-wide cooldown.
+
+    pub fn get_guild_last_ping(&self, guild_id: GuildId) -> u64 {
+        self.db
+            .query_row(
+                "SELECT timestamp FROM last_pings WHERE guild_id = ?1 AND list_id = 0",
+                params![guild_id.get()],
+                |row| row.get::<usize, u64>(0),
+            )
+            .optional()
+            .expect("Malformed SQL")
+            .unwrap_or(0)
+    }
+
+    pub fn get_list_last_ping(&self, list_id: ListId) -> u64 {
+        self.db
+            .query_row(
+                "SELECT timestamp FROM last_pings WHERE list_id = ?1",
+                params![list_id],
+                |row| row.get::<usize, u64>(0),
+            )
+            .optional()
+            .expect("Malformed SQL")
+            .unwrap_or(0)
+    }
+
+    pub fn set_last_ping(
+        &mut self,
+        guild_id: GuildId,
+        list_ids: &[ListId],
+        timestamp: u64,
+    ) -> Result<(), Error> {
+        let tx = self.db.transaction()?;
+        for list_id in std::iter::once(&0).chain(list_ids) {
+            tx.execute(
+                "INSERT INTO last_pings (guild_id, list_id, timestamp) VALUES (?1, ?2, ?3) \
+                ON CONFLICT(guild_id, list_id) DO UPDATE SET timestamp = excluded.timestamp",
+                params![guild_id.get(), list_id, timestamp],
+            )?;
+        }
+        tx.commit()
+    }
+
+    //ANCHOR Ping history
+    pub fn add_ping_history(&mut self, record: &PingRecord) -> Result<u64, Error> {
+        let tx = self.db.transaction()?;
+        tx.execute(
+            "INSERT INTO ping_history (guild_id, channel_id, user_id, member_count, timestamp) \
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                record.guild_id.get(),
+                record.channel_id.get(),
+                record.user_id.get(),
+                record.member_count,
+                record.timestamp
+            ],
+        )?;
+        let ping_id = tx.last_insert_rowid() as u64;
+        for list_id in &record.list_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO ping_history_lists (ping_id, list_id) VALUES (?1, ?2)",
+                params![ping_id, list_id],
+            )?;
+        }
+        for message_id in &record.message_ids {
+            tx.execute(
+                "INSERT INTO ping_history_messages (ping_id, message_id) VALUES (?1, ?2)",
+                params![ping_id, message_id.get()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(ping_id)
+    }
+
+    const PING_HISTORY_FILTER: &'static str = "ping_history.guild_id = :guid \
+        AND (:user_id = 0 OR ping_history.user_id = :user_id) \
+        AND (:list_id = 0 OR EXISTS (SELECT 1 FROM ping_history_lists \
+            WHERE ping_history_lists.ping_id = ping_history.id AND ping_history_lists.list_id = :list_id)) \
+        AND ping_history.timestamp >= :since AND ping_history.timestamp < :until";
+
+    pub fn count_ping_history(&mut self, guild_id: GuildId, filter: &PingHistoryFilter) -> usize {
+        self.db
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM ping_history WHERE {}",
+                    Database::PING_HISTORY_FILTER
+                ),
+                named_params! {
+                    ":guid": guild_id.get(),
+                    ":user_id": filter.user_id.map_or(0, UserId::get),
+                    ":list_id": filter.list_id.unwrap_or(0),
+                    ":since": filter.since,
+                    ":until": filter.until,
+                },
+                |row| row.get::<usize, usize>(0),
+            )
+            .unwrap()
+    }
+
+    /// Recorded pings matching the filter, newest first.
+    pub fn get_ping_history(
+        &mut self,
+        guild_id: GuildId,
+        filter: &PingHistoryFilter,
+        start: usize,
+        amount: usize,
+    ) -> Result<Vec<PingRecord>, Error> {
+        let mut stmt = self.db.prepare(&format!(
+            "SELECT id, channel_id, user_id, member_count, timestamp FROM ping_history \
+            WHERE {} ORDER BY timestamp DESC, id DESC LIMIT :amount OFFSET :start",
+            Database::PING_HISTORY_FILTER
+        ))?;
+        let mut rows = stmt.query(named_params! {
+            ":guid": guild_id.get(),
+            ":user_id": filter.user_id.map_or(0, UserId::get),
+            ":list_id": filter.list_id.unwrap_or(0),
+            ":since": filter.since,
+            ":until": filter.until,
+            ":amount": amount,
+            ":start": start,
+        })?;
+
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(PingRecord {
+                id: row.get::<usize, u64>(0)?,
+                guild_id,
+                channel_id: ChannelId::new(row.get::<usize, u64>(1)?),
+                user_id: UserId::new(row.get::<usize, u64>(2)?),
+                list_ids: vec![],
+                member_count: row.get::<usize, usize>(3)?,
+                timestamp: row.get::<usize, u64>(4)?,
+                message_ids: vec![],
+            });
+        }
+        for record in records.iter_mut() {
+            let mut stmt = self
+                .db
+                .prepare("SELECT list_id FROM ping_history_lists WHERE ping_id = ?1")?;
+            record.list_ids = stmt
+                .query_map(params![record.id], |row| row.get::<usize, u64>(0))?
+                .collect::<Result<Vec<ListId>, _>>()?;
+            let mut stmt = self.db.prepare(
+                "SELECT message_id FROM ping_history_messages WHERE ping_id = ?1 ORDER BY rowid",
+            )?;
+            record.message_ids = stmt
+                .query_map(params![record.id], |row| {
+                    row.get::<usize, u64>(0).map(MessageId::new)
+                })?
+                .collect::<Result<Vec<MessageId>, _>>()?;
+        }
+        Ok(records)
+    }
+
+    //ANCHOR Scheduled pings
+    pub fn add_scheduled_ping(&mut self, ping: &ScheduledPing) -> Result<u64, Error> {
+        self.db.execute(
+            "INSERT INTO scheduled_pings (guild_id, channel_id, user_id, queries, note, next_time, interval, queued, schedule) \
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                ping.guild_id.get(),
+                ping.channel_id.get(),
+                ping.user_id.get(),
+                ping.queries.join("\n"),
+                ping.note,
+                ping.next_time,
+                ping.interval,
+                ping.queued,
+                ping.schedule
+            ],
+        )?;
+        Ok(self.db.last_insert_rowid() as u64)
+    }
+
+    fn get_scheduled_pings_where(
+        &mut self,
+        condition: &str,
+        value: u64,
+    ) -> Result<Vec<ScheduledPing>, Error> {
+        let mut stmt = self.db.prepare(&format!(
+            "SELECT id, guild_id, channel_id, user_id, queries, note, next_time, interval, queued, schedule \
+            FROM scheduled_pings WHERE {} ORDER BY next_time ASC, id ASC",
+            condition
+        ))?;
+        let rows = stmt.query_map(params![value], |row| {
+            Ok(ScheduledPing {
+                id: row.get::<usize, u64>(0)?,
+                guild_id: GuildId::new(row.get::<usize, u64>(1)?),
+                channel_id: ChannelId::new(row.get::<usize, u64>(2)?),
+                user_id: UserId::new(row.get::<usize, u64>(3)?),
+                queries: row
+                    .get::<usize, String>(4)?
+                    .split('\n')
+                    .map(str::to_string)
+                    .collect(),
+                note: row.get::<usize, String>(5)?,
+                next_time: row.get::<usize, u64>(6)?,
+                interval: row.get::<usize, u64>(7)?,
+                queued: row.get::<usize, bool>(8)?,
+                schedule: row.get::<usize, Option<String>>(9)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_scheduled_pings(&mut self, guild_id: GuildId) -> Result<Vec<ScheduledPing>, Error> {
+        self.get_scheduled_pings_where("guild_id = ?1", guild_id.get())
+    }
+
+    pub fn get_due_scheduled_pings(&mut self, now: u64) -> Result<Vec<ScheduledPing>, Error> {
+        self.get_scheduled_pings_where("next_time <= ?1", now)
+    }
+
+    pub fn get_scheduled_ping(&mut self, id: u64) -> Result<Option<ScheduledPing>, Error> {
+        Ok(self.get_scheduled_pings_where("id = ?1", id)?.pop())
+    }
+
+    pub fn reschedule_ping(&mut self, id: u64, next_time: u64) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE scheduled_pings SET next_time = ?2 WHERE id = ?1",
+            params![id, next_time],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_scheduled_ping(&mut self, id: u64) -> Result<bool, Error> {
+        self.db
+            .execute("DELETE FROM scheduled_pings WHERE id = ?1", params![id])
+            .map(|removed| removed > 0)
+    }
+
+    //ANCHOR List functions
+    pub fn add_list(&mut self, guild_id: GuildId, name: &str) -> Option<ListId> {
+        self.alias_revision += 1;
+        let tx = self.db.transaction().unwrap();
+        let suc = tx
+            .execute(
+                "INSERT INTO lists (guild_id) VALUES (?1);",
+                params![guild_id.get()],
+            )
+            .unwrap();
+        if suc == 0 {
+            tx.finish().unwrap();
+            return None;
+        }
+        let list_id = tx.last_insert_rowid() as u64;
+        if !Database::add_alias(&tx, list_id, name) {
+            // The name is taken in this guild
+            tx.rollback().unwrap();
+            return None;
+        }
+        tx.commit().unwrap();
+        Some(list_id)
+    }
+
+    pub fn remove_list(&mut self, list_id: ListId) -> Result<bool, Error> {
+        self.alias_revision += 1;
+        self.remove_all_alias(list_id)?;
+        self.remove_all_members(list_id)?;
+        self.remove_proposal(list_id)?;
+        self.db.execute(
+            "DELETE FROM last_pings WHERE list_id = ?1",
+            params![list_id],
+        )?;
+        self.db.execute(
+            "DELETE FROM list_managers WHERE list_id = ?1",
+            params![list_id],
+        )?;
+        self.db.execute(
+            "DELETE FROM join_requests WHERE list_id = ?1",
+            params![list_id],
+        )?;
+        self.db.execute(
+            "DELETE FROM list_includes WHERE parent_id = ?1 OR child_id = ?1",
+            params![list_id],
+        )?;
+        let removed = self
+            .db
+            .execute("DELETE FROM lists WHERE id = ?1", params![list_id])?
+            > 0;
+        Database::update_search_entry(&self.db, list_id)?;
+        Ok(removed)
+    }
+
+    /// Hides a list everywhere while keeping its names, members and settings so it can be restored.
+    pub fn archive_list(&mut self, list_id: ListId, now: u64) -> Result<bool, Error> {
+        self.alias_revision += 1;
+        // A proposal that gets archived comes back as a regular list when restored
+        if self.remove_proposal(list_id)? {
+            self.set_pingable(list_id, PERMISSION::NEUTRAL);
+            self.set_joinable(list_id, PERMISSION::NEUTRAL);
+            self.set_visible(list_id, true);
+        }
+        self.db.execute(
+            "DELETE FROM join_requests WHERE list_id = ?1",
+            params![list_id],
+        )?;
+        Ok(self.db.execute(
+            "UPDATE lists SET archived_at = ?1 WHERE id = ?2 AND archived_at IS NULL",
+            params![now, list_id],
+        )? > 0)
+    }
+
+    pub fn restore_list(&mut self, list_id: ListId) -> Result<bool, Error> {
+        self.alias_revision += 1;
+        Ok(self.db.execute(
+            "UPDATE lists SET archived_at = NULL WHERE id = ?1 AND archived_at IS NOT NULL",
+            params![list_id],
+        )? > 0)
+    }
+
+    pub fn is_archived(&self, list_id: ListId) -> bool {
+        self.db
+            .query_row(
+                "SELECT archived_at IS NOT NULL FROM lists WHERE id = ?1",
+                params![list_id],
+                |row| row.get::<usize, bool>(0),
+            )
+            .optional()
+            .unwrap()
+            .unwrap_or(false)
+    }
+
+    /// The most recently archived list going by a name.
+    pub fn get_archived_list_id_by_name(
+        &mut self,
+        list_name: &str,
+        guild_id: GuildId,
+    ) -> Option<ListId> {
+        self.db
+            .query_row(
+                "SELECT lists.id FROM lists, alias WHERE alias.key = ?1 AND alias.list_id = lists.id \
+                AND lists.guild_id = ?2 AND lists.archived_at IS NOT NULL \
+                ORDER BY lists.archived_at DESC LIMIT 1",
+                params![Database::alias_key(list_name), guild_id.get()],
+                |row| row.get::<usize, u64>(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    pub fn get_archived_list_names(
+        &mut self,
+        guild_id: GuildId,
+        amount: usize,
+        filter: &str,
+    ) -> Vec<String> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT DISTINCT alias.name FROM lists, alias WHERE alias.list_id = lists.id \
+                AND lists.guild_id = ?1 AND lists.archived_at IS NOT NULL \
+                AND alias.name LIKE '%' || ?2 || '%' \
+                ORDER BY alias.name ASC LIMIT ?3",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get(), filter, amount], |row| {
+                row.get::<usize, String>(0)
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<String>, _>>().unwrap()
+    }
+
+    /// Deletes archived lists that have outlived the retention period of their guild.
+    pub fn purge_archived_lists(&mut self, now: u64) -> Result<usize, Error> {
+        let expired = {
+            let mut stmt = self.db.prepare(
+                "SELECT lists.id FROM lists, guilds WHERE lists.guild_id = guilds.id \
+                AND lists.archived_at IS NOT NULL \
+                AND lists.archived_at + guilds.archive_retention <= ?1",
+            )?;
+            let rows = stmt.query_map(params![now], |row| row.get::<usize, u64>(0))?;
+            rows.collect::<Result<Vec<ListId>, _>>()?
+        };
+        for list_id in &expired {
+            self.remove_list(*list_id)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Moves the members, names and managers of one list into another and deletes the emptied
+    /// list. Returns how many members were new to the target, or None when the lists include each
+    /// other through a third list so merging them would make the target include itself.
+    pub fn merge_lists(
+        &mut self,
+        source_id: ListId,
+        target_id: ListId,
+    ) -> Result<Option<usize>, Error> {
+        self.alias_revision += 1;
+        let tx = self.db.transaction()?;
+        // Members of both lists keep the membership they had on the target
+        let moved = tx.execute(
+            "INSERT OR IGNORE INTO memberships (user_id, list_id, expires_at, duration) \
+            SELECT user_id, ?2, expires_at, duration FROM memberships WHERE list_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE alias SET list_id = ?2 WHERE list_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO list_managers (list_id, user_id) \
+            SELECT ?2, user_id FROM list_managers WHERE list_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE join_requests SET list_id = ?2 WHERE list_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE list_includes SET parent_id = ?2 WHERE parent_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE list_includes SET child_id = ?2 WHERE child_id = ?1",
+            params![source_id, target_id],
+        )?;
+        tx.execute(
+            "DELETE FROM list_includes WHERE parent_id = ?1 AND child_id = ?1",
+            params![target_id],
+        )?;
+        let cycle = tx
+            .query_row(
+                "WITH RECURSIVE descendants(id) AS ( \
+                    SELECT child_id FROM list_includes WHERE parent_id = ?1 \
+                    UNION SELECT list_includes.child_id FROM list_includes, descendants WHERE list_includes.parent_id = descendants.id) \
+                SELECT 1 FROM descendants WHERE id = ?1",
+                params![target_id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if cycle {
+            tx.rollback()?;
+            return Ok(None);
+        }
+        for statement in [
+            "DELETE FROM alias WHERE list_id = ?1",
+            "DELETE FROM memberships WHERE list_id = ?1",
+            "DELETE FROM list_managers WHERE list_id = ?1",
+            "DELETE FROM join_requests WHERE list_id = ?1",
+            "DELETE FROM list_includes WHERE parent_id = ?1 OR child_id = ?1",
+            "DELETE FROM proposals WHERE list_id = ?1",
+            "DELETE FROM last_pings WHERE list_id = ?1",
+            "DELETE FROM lists WHERE id = ?1",
+        ] {
+            tx.execute(statement, params![source_id])?;
+        }
+        Database::update_search_entry(&tx, source_id)?;
+        Database::update_search_entry(&tx, target_id)?;
+        tx.commit()?;
+        Ok(Some(moved))
+    }
+
+    /// Creates a new list in the same category and moves the given members of a list onto it.
+    /// Returns the new list and how many members were moved, or None when the name is taken.
+    pub fn split_list(
+        &mut self,
+        source_id: ListId,
+        name: &str,
+        member_ids: &[UserId],
+    ) -> Result<Option<(ListId, usize)>, Error> {
+        self.alias_revision += 1;
+        let tx = self.db.transaction()?;
+        tx.execute(
+            "INSERT INTO lists (guild_id, category_id) SELECT guild_id, category_id FROM lists WHERE id = ?1",
+            params![source_id],
+        )?;
+        let list_id = tx.last_insert_rowid() as u64;
+        if !Database::add_alias(&tx, list_id, name) {
+            tx.rollback()?;
+            return Ok(None);
+        }
+        let mut moved = 0;
+        for member_id in member_ids {
+            moved += tx.execute(
+                "UPDATE memberships SET list_id = ?1 WHERE list_id = ?2 AND user_id = ?3",
+                params![list_id, source_id, member_id.get()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(Some((list_id, moved)))
+    }
+
+    pub fn rename_alias(&mut self, list_id: ListId, name: &str, new_name: &str) -> bool {
+        self.alias_revision += 1;
+        let renamed = self
+            .db
+            .execute(
+                "UPDATE OR IGNORE alias SET name = ?3, key = ?4 WHERE list_id = ?1 AND key = ?2",
+                params![
+                    list_id,
+                    Database::alias_key(name),
+                    new_name,
+                    Database::alias_key(new_name)
+                ],
+            )
+            .unwrap()
+            > 0;
+        Database::update_search_entry(&self.db, list_id).unwrap();
+        renamed
+    }
+
+    //List config
+    pub fn set_pingable(&mut self, list_id: ListId, pingable: PERMISSION) -> bool {
+        self.alias_revision += 1;
+        self.db
+            .execute(
+                "UPDATE lists SET ping_permission = ?1 WHERE id = ?2",
+                params![pingable as u64, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn set_joinable(&mut self, list_id: ListId, joinable: PERMISSION) -> bool {
+        self.alias_revision += 1;
+        self.db
+            .execute(
+                "UPDATE lists SET join_permission = ?1 WHERE id = ?2",
+                params![joinable as u64, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn set_visible(&mut self, list_id: ListId, visible: bool) -> bool {
+        self.alias_revision += 1;
+        self.db
+            .execute(
+                "UPDATE lists SET visible = ?1 WHERE id = ?2",
+                params![visible, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn set_delivery_mode(&mut self, list_id: ListId, mode: DeliveryMode) -> bool {
+        self.db
+            .execute(
+                "UPDATE lists SET delivery_mode = ?1 WHERE id = ?2",
+                params![mode as u64, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    /// Links a list to a discord role, or unlinks it when given `None`.
+    pub fn set_list_role(&mut self, list_id: ListId, role_id: Option<RoleId>) -> bool {
+        self.db
+            .execute(
+                "UPDATE lists SET role_id = ?1 WHERE id = ?2",
+                params![role_id.map(|r| r.get()), list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn set_requires_approval(&mut self, list_id: ListId, value: bool) -> bool {
+        self.db
+            .execute(
+                "UPDATE lists SET require_approval = ?1 WHERE id = ?2",
+                params![value, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn set_description(&mut self, list_id: ListId, value: &str) -> bool {
+        let changed = self
+            .db
+            .execute(
+                "UPDATE lists SET description = ?1 WHERE id = ?2",
+                params![value, list_id],
+            )
+            .unwrap()
+            > 0;
+        Database::update_search_entry(&self.db, list_id).unwrap();
+        changed
+    }
+    pub fn set_cooldown(&mut self, list_id: ListId, value: i64) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "UPDATE lists SET cooldown = ?1 WHERE id = ?2",
+            params![value, list_id],
+        )? > 0)
+    }
+
+    /// Rewrites the full-text search entry of a list from its current names and description.
+    fn update_search_entry(db: &Connection, list_id: ListId) -> Result<(), Error> {
+        db.execute("DELETE FROM list_search WHERE rowid = ?1", params![list_id])?;
+        db.execute(
+            "INSERT INTO list_search (rowid, names, description) \
+            SELECT lists.id, (SELECT group_concat(alias.name, ' ') FROM alias WHERE alias.list_id = lists.id), lists.description \
+            FROM lists WHERE lists.id = ?1",
+            params![list_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_alias_inline(&mut self, list_id: ListId, name: &str) -> bool {
+        self.alias_revision += 1;
+        Database::add_alias(&self.db, list_id, name)
+    }
+
+    /// What alias lookups compare, so names differing only in case or Unicode form are the same.
+    pub fn alias_key(name: &str) -> String {
+        name.trim().nfkc().flat_map(char::to_lowercase).collect()
+    }
+
+    /// Fails when the name is already used in the guild of the list.
+    pub fn add_alias(db: &Connection, list_id: ListId, name: &str) -> bool {
+        match db.execute(
+            "INSERT INTO alias (list_id, guild_id, name, key) \
+            SELECT ?1, guild_id, ?2, ?3 FROM lists WHERE id = ?1",
+            params![list_id, name, Database::alias_key(name)],
+        ) {
+            Ok(_) => {
+                Database::update_search_entry(db, list_id).unwrap();
+                true
+            }
+            Err(Error::SqliteFailure(
+                rusqlite::ffi::Error {
+                    code: _,
+                    extended_code: 2067,
+                },
+                _,
+            )) => false, // Unique constraint violation, alias already exists in this guild
+            Err(a) => Err(a).unwrap(),
+        }
+    }
+
+    pub fn remove_alias(
+        &mut self,
+        db: Option<&Connection>,
+        list_id: ListId,
+        name: &str,
+    ) -> Result<(), Error> {
+        self.alias_revision += 1;
+        let db = db.unwrap_or(&self.db);
+        db.execute(
+            "DELETE FROM alias WHERE list_id = ?1 AND key = ?2",
+            params![list_id, Database::alias_key(name)],
+        )?;
+        Database::update_search_entry(db, list_id)
+    }
+
+    fn remove_all_alias(&mut self, list_id: ListId) -> Result<(), Error> {
+        self.db
+            .execute("DELETE FROM alias WHERE list_id = ?1", params![list_id])?;
+        Database::update_search_entry(&self.db, list_id)
+    }
+
+    fn remove_all_members(&mut self, list_id: ListId) -> Result<(), Error> {
+        self.db.execute(
+            "DELETE FROM memberships WHERE list_id = ?1",
+            params![list_id],
+        )?;
+        Ok(())
+    }
+
+    //Getters
+
+    pub fn get_list(&self, list_id: ListId) -> Option<PingList> {
+        self.db
+            .query_row(
+                "SELECT lists.guild_id, lists.description, lists.visible, lists.join_permission, lists.ping_permission, lists.cooldown, categories.name \
+                FROM lists LEFT JOIN categories ON categories.id = lists.category_id WHERE lists.id = ?1",
+                params![list_id],
+                |row| {
+                    Ok(PingList {
+                        id: list_id,
+                        guild_id: GuildId::new(row.get::<usize, u64>(0)?),
+                        description: row.get::<usize, String>(1)?,
+                        visible: row.get::<usize, bool>(2)?,
+                        cooldown: row.get::<usize, i64>(5)?,
+                        join_permission: PERMISSION::fromint(row.get::<usize, u64>(3)?),
+                        ping_permission: PERMISSION::fromint(row.get::<usize, u64>(4)?),
+                        category: row.get::<usize, Option<String>>(6)?,
+                    })
+                },
+            )
+            .optional()
+            .unwrap()
+    }
+
+    pub fn get_list_permissions(&self, list_id: ListId) -> (i64, PERMISSION, PERMISSION) {
+        self.db
+            .query_row(
+                "SELECT cooldown, join_permission, ping_permission FROM lists WHERE id=?1",
+                params![list_id],
+                |row| {
+                    Ok((
+                        row.get::<usize, i64>(0)?,
+                        PERMISSION::fromint(row.get::<usize, u64>(1)?),
+                        PERMISSION::fromint(row.get::<usize, u64>(2)?),
+                    ))
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn get_delivery_mode(&self, list_id: ListId) -> DeliveryMode {
+        self.db
+            .query_row(
+                "SELECT delivery_mode FROM lists WHERE id=?1",
+                params![list_id],
+                |row| row.get::<usize, u64>(0),
+            )
+            .map(DeliveryMode::fromint)
+            .unwrap()
+    }
+
+    pub fn get_requires_approval(&self, list_id: ListId) -> bool {
+        self.db
+            .query_row(
+                "SELECT require_approval FROM lists WHERE id=?1",
+                params![list_id],
+                |row| row.get::<usize, bool>(0),
+            )
+            .unwrap()
+    }
+
+    pub fn get_list_role(&self, list_id: ListId) -> Option<RoleId> {
+        self.db
+            .query_row(
+                "SELECT role_id FROM lists WHERE id=?1",
+                params![list_id],
+                |row| row.get::<usize, Option<u64>>(0),
+            )
+            .optional()
+            .unwrap()
+            .flatten()
+            .map(RoleId::new)
+    }
+
+    pub fn get_role_linked_lists(&mut self, guild_id: GuildId) -> Vec<(ListId, RoleId)> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id, role_id FROM lists WHERE guild_id=?1 AND role_id IS NOT NULL AND archived_at IS NULL")
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get()], |row| {
+                Ok((
+                    row.get::<usize, u64>(0)?,
+                    RoleId::new(row.get::<usize, u64>(1)?),
+                ))
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<(ListId, RoleId)>, _>>().unwrap()
+    }
+
+    pub fn get_list_id_by_name(&mut self, list_name: &str, guild_id: GuildId) -> Option<ListId> {
+        self.db.query_row(
+                "SELECT lists.id FROM lists, alias WHERE alias.key=?1 AND alias.list_id = lists.id AND lists.guild_id=?2 AND lists.archived_at IS NULL",
+                params![Database::alias_key(list_name), guild_id.get()], |row| row.get::<usize, u64>(0)
+            ).optional().unwrap()
+    }
+
+    /// The list using a name in a guild, archived lists included, and whether it is archived.
+    pub fn get_alias_owner(&self, name: &str, guild_id: GuildId) -> Option<(ListId, bool)> {
+        self.db
+            .query_row(
+                "SELECT lists.id, lists.archived_at IS NOT NULL FROM lists, alias \
+                WHERE alias.key = ?1 AND alias.guild_id = ?2 AND alias.list_id = lists.id",
+                params![Database::alias_key(name), guild_id.get()],
+                |row| Ok((row.get::<usize, u64>(0)?, row.get::<usize, bool>(1)?)),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    pub fn get_alias_revision(&self) -> u64 {
+        self.alias_revision
+    }
+
+    /// Every name of the active lists of a guild with the permissions of its list.
+    pub fn get_alias_index_entries(
+        &self,
+        guild_id: GuildId,
+    ) -> Vec<(String, ListId, PERMISSION, PERMISSION, bool)> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT alias.name, lists.id, lists.ping_permission, lists.join_permission, lists.visible \
+                FROM lists, alias WHERE alias.list_id = lists.id AND lists.guild_id = ?1 \
+                AND lists.archived_at IS NULL",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get()], |row| {
+                Ok((
+                    row.get::<usize, String>(0)?,
+                    row.get::<usize, u64>(1)?,
+                    PERMISSION::fromint(row.get::<usize, u64>(2)?),
+                    PERMISSION::fromint(row.get::<usize, u64>(3)?),
+                    row.get::<usize, bool>(4)?,
+                ))
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
     pub fn get_list_exists(&mut self, list_id: ListId) -> bool {
         self.db
             .query_row(
@@ -361,22 +1640,42 @@ impl Database {
             .expect("Element is not string or index is incorrect")
     }
 
+    /// A full-text query matching every word of a filter as a prefix, None without words.
+    fn search_query(filter: &str) -> Option<String> {
+        let words: Vec<String> = filter
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+
     pub fn get_lists_by_search(
         &mut self,
         guild_id: GuildId,
         filter: &str,
+        category: Option<u64>,
         show_hidden: bool,
     ) -> Result<Vec<PingList>, Error> {
-        let lists_query = "SELECT lists.id, lists.description, lists.visible, lists.join_permission, lists.ping_permission, lists.cooldown \
-                FROM lists, alias \
+        // Without a filter lists come grouped by category, uncategorized lists last. With one,
+        // lists whose names contain it come first, then the best full-text matches.
+        let lists_query = "SELECT lists.id, lists.description, lists.visible, lists.join_permission, lists.ping_permission, lists.cooldown, categories.name \
+                FROM lists JOIN alias ON alias.list_id = lists.id \
+                LEFT JOIN categories ON categories.id = lists.category_id \
+                LEFT JOIN ( \
+                    SELECT rowid AS id, bm25(list_search, 10.0, 1.0) AS score FROM list_search \
+                    WHERE :query IS NOT NULL AND list_search MATCH :query) AS hits ON hits.id = lists.id \
                 WHERE lists.guild_id=:guid \
-                AND alias.name LIKE '%' || :filter || '%' \
-                AND alias.list_id = lists.id \
+                AND lists.archived_at IS NULL \
+                AND (alias.name LIKE '%' || :filter || '%' OR hits.id IS NOT NULL) \
+                AND (:category IS NULL OR lists.category_id = :category) \
                 AND (lists.visible = 1 OR :show_hidden) \
-                ORDER BY alias.name ASC";
+                GROUP BY lists.id \
+                ORDER BY :filter = '' AND categories.name IS NULL, CASE WHEN :filter = '' THEN categories.name END ASC, \
+                MAX(alias.name LIKE '%' || :filter || '%') DESC, MIN(hits.score) IS NULL, MIN(hits.score) ASC, MIN(alias.name) ASC";
         let mut stmt = self.db.prepare(lists_query)?;
         let mut rows = stmt.query(
-                named_params! { ":guid": guild_id.get(), ":filter": filter, ":show_hidden": show_hidden },
+                named_params! { ":guid": guild_id.get(), ":filter": filter, ":query": Database::search_query(filter), ":category": category, ":show_hidden": show_hidden },
             )?;
 
         let mut lists = Vec::new();
@@ -389,6 +1688,7 @@ impl Database {
                 cooldown: row.get::<usize, i64>(5)?,
                 join_permission: PERMISSION::fromint(row.get::<usize, u64>(3)?),
                 ping_permission: PERMISSION::fromint(row.get::<usize, u64>(4)?),
+                category: row.get::<usize, Option<String>>(6)?,
             });
         }
         Ok(lists)
@@ -398,6 +1698,7 @@ impl Database {
         &mut self,
         guild_id: GuildId,
         filter: &str,
+        category: Option<u64>,
         show_hidden: bool,
     ) -> usize {
         self.db
@@ -405,10 +1706,13 @@ impl Database {
                     "SELECT COUNT(DISTINCT lists.id) \
                     FROM lists, alias \
                     WHERE lists.guild_id=:guid \
-                    AND alias.name LIKE '%' || :filter || '%' \
+                    AND lists.archived_at IS NULL \
+                    AND (alias.name LIKE '%' || :filter || '%' OR lists.id IN ( \
+                        SELECT rowid FROM list_search WHERE :query IS NOT NULL AND list_search MATCH :query)) \
                     AND alias.list_id = lists.id \
+                    AND (:category IS NULL OR lists.category_id = :category) \
                     AND (lists.visible = 1 OR :show_hidden)",
-                    named_params! { ":guid": guild_id.get(), ":filter": filter, ":show_hidden": show_hidden},
+                    named_params! { ":guid": guild_id.get(), ":filter": filter, ":query": Database::search_query(filter), ":category": category, ":show_hidden": show_hidden},
                     |row| row.get::<usize, usize>(0),
                 )
                 .unwrap_or(0)
@@ -425,6 +1729,7 @@ impl Database {
         let lists_query = "SELECT alias.name \
                 FROM lists, alias \
                 WHERE lists.guild_id=:guid \
+                AND lists.archived_at IS NULL \
                 AND alias.name LIKE '%' || :filter || '%' \
                 AND alias.list_id = lists.id \
                 AND (NOT lists.ping_permission = :permissiondeny OR :show_all)
@@ -440,68 +1745,6 @@ impl Database {
             .expect("Element is not string or index is incorrect")
     }
 
-    pub fn get_list_membership_by_search(
-        &mut self,
-        guild_id: GuildId,
-        user_id: UserId,
-        amount: usize,
-        filter: &str,
-        show_all: bool,
-    ) -> Vec<String> {
-        let lists_query = "SELECT alias.name \
-                FROM lists, alias \
-                WHERE lists.guild_id=:guid \
-                AND alias.name LIKE '%' || :filter || '%' \
-                AND alias.list_id = lists.id \
-                AND (NOT lists.ping_permission = :permissiondeny OR :show_all)
-                AND EXISTS ( \
-                    SELECT id FROM memberships WHERE \
-                    memberships.user_id = :user \
-                    AND memberships.list_id = lists.id) \
-                ORDER BY alias.name ASC \
-                LIMIT 0, :amt";
-        let mut stmt = self
-            .db
-            .prepare(lists_query)
-            .expect("Sql statement malformed");
-        let rows = stmt.query_map(
-                named_params! { ":guid": guild_id.get(), ":filter": filter, ":amt": amount, ":show_all": show_all, ":permissiondeny": PERMISSION::DENY as u64, ":user": user_id.get() },
-                |row| row.get::<usize, String>(0)
-            ).expect("Error binding parameters");
-
-        rows.collect::<Result<Vec<String>, _>>()
-            .expect("Element is not string or index is incorrect")
-    }
-
-    pub fn get_list_joinable_by_search(
-        &mut self,
-        guild_id: GuildId,
-        user_id: UserId,
-        amount: usize,
-        filter: &str,
-        show_all: bool,
-    ) -> Vec<String> {
-        let lists_query = "SELECT alias.name \
-                FROM lists, alias \
-                WHERE lists.guild_id=:guid \
-                AND alias.name LIKE '%' || :filter || '%' \
-                AND alias.list_id = lists.id \
-                AND (NOT lists.join_permission = :permissiondeny OR :show_all)
-                AND NOT EXISTS ( \
-                    SELECT id FROM memberships WHERE \
-                    memberships.user_id = :user \
-                    AND memberships.list_id = lists.id) \
-                ORDER BY alias.name ASC \
-                LIMIT 0, :amt";
-        let mut stmt = self.db.prepare(lists_query).expect("Sql query malformed");
-        let rows = stmt.query_map(
-                named_params! { ":guid": guild_id.get(), ":filter": filter, ":amt": amount, ":show_all": show_all, ":permissiondeny": PERMISSION::DENY as u64, ":user": user_id.get() },
-                |row| row.get::<usize, String>(0)
-            ).expect("Unable to bind parameters to query");
-
-        rows.collect::<Result<Vec<String>, _>>()
-            .expect("Element is not string or index is incorrect")
-    }
     // List memberships
 
     pub fn get_lists_with_member(
@@ -509,7 +1752,7 @@ impl Database {
         guild_id: GuildId,
         member_id: UserId,
     ) -> Result<Vec<u64>, Error> {
-        let mut stmt = self.db.prepare("SELECT lists.id FROM lists, memberships WHERE lists.id=memberships.list_id AND memberships.user_id=? AND lists.guild_id=?")?;
+        let mut stmt = self.db.prepare("SELECT lists.id FROM lists, memberships WHERE lists.id=memberships.list_id AND memberships.user_id=? AND lists.guild_id=? AND lists.archived_at IS NULL")?;
         let mut rows = stmt.query(params![member_id.get(), guild_id.get()])?;
         let mut lists = Vec::new();
         while let Some(row) = rows.next()? {
@@ -519,15 +1762,61 @@ impl Database {
     }
 
     pub fn get_members_in_list(&mut self, list_id: ListId) -> Vec<UserId> {
-        let mut stmt = self.db.prepare("SELECT memberships.user_id FROM lists, memberships WHERE lists.id=memberships.list_id AND memberships.list_id=?").unwrap();
+        let mut stmt = self.db.prepare("SELECT memberships.user_id FROM lists, memberships WHERE lists.id=memberships.list_id AND memberships.list_id=? \
+            AND NOT EXISTS (SELECT 1 FROM departures WHERE departures.user_id = memberships.user_id AND departures.guild_id = lists.guild_id)").unwrap();
+        let rows = stmt
+            .query_map(params![list_id], |row| {
+                row.get::<usize, u64>(0).map(|id| UserId::new(id))
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<UserId>, _>>().unwrap()
+    }
+
+    /// Everyone that is on a list directly or through the lists it includes.
+    pub fn get_effective_members_in_list(&mut self, list_id: ListId) -> Vec<UserId> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "WITH RECURSIVE included(id) AS ( \
+                    SELECT ?1 \
+                    UNION SELECT list_includes.child_id FROM list_includes, included WHERE list_includes.parent_id = included.id) \
+                SELECT DISTINCT memberships.user_id FROM lists, memberships \
+                WHERE lists.id = memberships.list_id AND memberships.list_id IN (SELECT id FROM included) \
+                AND lists.archived_at IS NULL \
+                AND NOT EXISTS (SELECT 1 FROM departures WHERE departures.user_id = memberships.user_id AND departures.guild_id = lists.guild_id)",
+            )
+            .unwrap();
         let rows = stmt
             .query_map(params![list_id], |row| {
-                row.get::<usize, u64>(0).map(|id| UserId::new(id))
+                row.get::<usize, u64>(0).map(UserId::new)
             })
             .unwrap();
         rows.collect::<Result<Vec<UserId>, _>>().unwrap()
     }
 
+    /// Lists a member is on only through an included list, each with one of the lists of the
+    /// member it was reached through.
+    pub fn get_inherited_lists_with_member(
+        &mut self,
+        guild_id: GuildId,
+        member_id: UserId,
+    ) -> Result<Vec<(ListId, ListId)>, Error> {
+        let mut stmt = self.db.prepare(
+            "WITH RECURSIVE reach(id, via) AS ( \
+                SELECT list_includes.parent_id, memberships.list_id FROM memberships, list_includes \
+                WHERE memberships.user_id = ?1 AND list_includes.child_id = memberships.list_id \
+                UNION SELECT list_includes.parent_id, reach.via FROM list_includes, reach WHERE list_includes.child_id = reach.id) \
+            SELECT reach.id, MIN(reach.via) FROM reach, lists WHERE lists.id = reach.id AND lists.guild_id = ?2 \
+            AND lists.archived_at IS NULL \
+            AND reach.id NOT IN (SELECT list_id FROM memberships WHERE user_id = ?1) \
+            GROUP BY reach.id",
+        )?;
+        let rows = stmt.query_map(params![member_id.get(), guild_id.get()], |row| {
+            Ok((row.get::<usize, u64>(0)?, row.get::<usize, u64>(1)?))
+        })?;
+        rows.collect()
+    }
+
     pub fn add_member(&mut self, member_id: UserId, list_id: ListId) -> JoinResult {
         let a = self.db.execute(
             "INSERT INTO memberships (user_id, list_id) VALUES (?1, ?2)",
@@ -559,6 +1848,388 @@ impl Database {
             params![member_id.get(), list_id],
         )? > 0)
     }
+    /// Makes the memberships of role-backed lists match the roles a member currently holds.
+    pub fn sync_role_memberships(
+        &mut self,
+        guild_id: GuildId,
+        member_id: UserId,
+        roles: &[RoleId],
+    ) {
+        for (list_id, role_id) in self.get_role_linked_lists(guild_id) {
+            if roles.contains(&role_id) {
+                self.db
+                    .execute(
+                        "INSERT OR IGNORE INTO memberships (user_id, list_id) VALUES (?1, ?2)",
+                        params![member_id.get(), list_id],
+                    )
+                    .expect("SQL statement malformed or SQL error");
+            } else {
+                self.remove_member(member_id, list_id)
+                    .expect("Failed to remove membership.");
+            }
+        }
+    }
+
+    /// Makes a membership end `duration` seconds after `now`, or last indefinitely with `None`.
+    pub fn set_membership_expiry(
+        &mut self,
+        member_id: UserId,
+        list_id: ListId,
+        now: u64,
+        duration: Option<u64>,
+    ) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "UPDATE memberships SET expires_at = ?1, duration = ?2 WHERE user_id = ?3 AND list_id = ?4",
+            params![duration.map(|d| now + d), duration, member_id.get(), list_id],
+        )? > 0)
+    }
+
+    /// Removes every membership that expired before `now`, returning the guild, list, member
+    /// and original duration of each.
+    pub fn remove_expired_memberships(
+        &mut self,
+        now: u64,
+    ) -> Result<Vec<(GuildId, ListId, UserId, u64)>, Error> {
+        let tx = self.db.transaction()?;
+        let expired = {
+            let mut stmt = tx.prepare(
+                "SELECT lists.guild_id, memberships.list_id, memberships.user_id, memberships.duration \
+                FROM memberships, lists WHERE memberships.list_id = lists.id AND memberships.expires_at <= ?1",
+            )?;
+            let rows = stmt.query_map(params![now], |row| {
+                Ok((
+                    GuildId::new(row.get::<usize, u64>(0)?),
+                    row.get::<usize, u64>(1)?,
+                    UserId::new(row.get::<usize, u64>(2)?),
+                    row.get::<usize, Option<u64>>(3)?.unwrap_or(0),
+                ))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        tx.execute(
+            "DELETE FROM memberships WHERE expires_at <= ?1",
+            params![now],
+        )?;
+        tx.commit()?;
+        Ok(expired)
+    }
+
+    pub fn has_member(&self, member_id: UserId, list_id: ListId) -> bool {
+        self.db
+            .query_row(
+                "SELECT 1 FROM memberships WHERE user_id = ?1 AND list_id = ?2",
+                params![member_id.get(), list_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .unwrap()
+            .is_some()
+    }
+
+    //ANCHOR list inclusion functions
+
+    /// Whether including `child` in `parent` would make a list include itself.
+    pub fn would_create_cycle(&self, parent_id: ListId, child_id: ListId) -> bool {
+        self.db
+            .query_row(
+                "WITH RECURSIVE descendants(id) AS ( \
+                    SELECT ?1 \
+                    UNION SELECT list_includes.child_id FROM list_includes, descendants WHERE list_includes.parent_id = descendants.id) \
+                SELECT 1 FROM descendants WHERE id = ?2",
+                params![child_id, parent_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .unwrap()
+            .is_some()
+    }
+
+    pub fn add_list_include(&mut self, parent_id: ListId, child_id: ListId) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "INSERT OR IGNORE INTO list_includes (parent_id, child_id) VALUES (?1, ?2)",
+            params![parent_id, child_id],
+        )? > 0)
+    }
+
+    pub fn remove_list_include(
+        &mut self,
+        parent_id: ListId,
+        child_id: ListId,
+    ) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "DELETE FROM list_includes WHERE parent_id = ?1 AND child_id = ?2",
+            params![parent_id, child_id],
+        )? > 0)
+    }
+
+    /// The lists directly included in a list.
+    pub fn get_included_lists(&mut self, list_id: ListId) -> Vec<ListId> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT child_id FROM list_includes WHERE parent_id = ?1")
+            .unwrap();
+        let rows = stmt
+            .query_map(params![list_id], |row| row.get::<usize, u64>(0))
+            .unwrap();
+        rows.collect::<Result<Vec<ListId>, _>>().unwrap()
+    }
+
+    //ANCHOR category functions
+
+    /// Creates a category, returns `None` when the guild already has one with that name.
+    pub fn add_category(&mut self, guild_id: GuildId, name: &str) -> Result<Option<u64>, Error> {
+        let inserted = self.db.execute(
+            "INSERT OR IGNORE INTO categories (guild_id, name) VALUES (?1, ?2)",
+            params![guild_id.get(), name],
+        )?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.db.last_insert_rowid() as u64))
+    }
+
+    pub fn rename_category(&mut self, category_id: u64, name: &str) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "UPDATE OR IGNORE categories SET name = ?1 WHERE id = ?2",
+            params![name, category_id],
+        )? > 0)
+    }
+
+    /// Deletes a category, its lists become uncategorized.
+    pub fn remove_category(&mut self, category_id: u64) -> Result<bool, Error> {
+        self.db.execute(
+            "UPDATE lists SET category_id = NULL WHERE category_id = ?1",
+            params![category_id],
+        )?;
+        Ok(self
+            .db
+            .execute("DELETE FROM categories WHERE id = ?1", params![category_id])?
+            > 0)
+    }
+
+    pub fn get_category_id(&self, guild_id: GuildId, name: &str) -> Option<u64> {
+        self.db
+            .query_row(
+                "SELECT id FROM categories WHERE guild_id = ?1 AND name = ?2",
+                params![guild_id.get(), name],
+                |row| row.get::<usize, u64>(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    pub fn get_category_name(&self, category_id: u64) -> Option<String> {
+        self.db
+            .query_row(
+                "SELECT name FROM categories WHERE id = ?1",
+                params![category_id],
+                |row| row.get::<usize, String>(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    pub fn get_category_names(&mut self, guild_id: GuildId, filter: &str) -> Vec<String> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT name FROM categories WHERE guild_id = ?1 AND name LIKE '%' || ?2 || '%' ORDER BY name ASC",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get(), filter], |row| {
+                row.get::<usize, String>(0)
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<String>, _>>().unwrap()
+    }
+
+    pub fn set_list_category(&mut self, list_id: ListId, category_id: Option<u64>) -> bool {
+        self.db
+            .execute(
+                "UPDATE lists SET category_id = ?1 WHERE id = ?2",
+                params![category_id, list_id],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn get_lists_in_category(&mut self, category_id: u64) -> Vec<ListId> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id FROM lists WHERE category_id = ?1")
+            .unwrap();
+        let rows = stmt
+            .query_map(params![category_id], |row| row.get::<usize, u64>(0))
+            .unwrap();
+        rows.collect::<Result<Vec<ListId>, _>>().unwrap()
+    }
+
+    //ANCHOR join request functions
+
+    /// Stores a request to join a list, returns `None` when the member already asked or was
+    /// denied recently.
+    pub fn add_join_request(
+        &mut self,
+        list_id: ListId,
+        member_id: UserId,
+        timestamp: u64,
+        duration: Option<u64>,
+    ) -> Result<Option<u64>, Error> {
+        self.db.execute(
+            "DELETE FROM join_requests WHERE list_id = ?1 AND user_id = ?2 \
+            AND (denied_at IS NULL AND timestamp + ?4 <= ?3 OR denied_at + ?5 <= ?3)",
+            params![
+                list_id,
+                member_id.get(),
+                timestamp,
+                JOIN_REQUEST_EXPIRY,
+                JOIN_REQUEST_COOLDOWN
+            ],
+        )?;
+        let inserted = self.db.execute(
+            "INSERT OR IGNORE INTO join_requests (list_id, user_id, timestamp, duration) VALUES (?1, ?2, ?3, ?4)",
+            params![list_id, member_id.get(), timestamp, duration],
+        )?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.db.last_insert_rowid() as u64))
+    }
+
+    /// When a member whose request to join a list was denied may ask again, if they have to wait.
+    pub fn get_join_request_cooldown(
+        &self,
+        list_id: ListId,
+        member_id: UserId,
+        now: u64,
+    ) -> Option<u64> {
+        self.db
+            .query_row(
+                "SELECT denied_at + ?3 FROM join_requests \
+                WHERE list_id = ?1 AND user_id = ?2 AND denied_at + ?3 > ?4",
+                params![list_id, member_id.get(), JOIN_REQUEST_COOLDOWN, now],
+                |row| row.get::<usize, u64>(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    /// The list, requester and requested membership duration of an undecided join request.
+    pub fn get_join_request(
+        &self,
+        id: u64,
+    ) -> Result<Option<(ListId, UserId, Option<u64>)>, Error> {
+        self.db
+            .query_row(
+                "SELECT list_id, user_id, duration FROM join_requests \
+                WHERE id = ?1 AND denied_at IS NULL",
+                params![id],
+                |row| {
+                    Ok((
+                        row.get::<usize, u64>(0)?,
+                        UserId::new(row.get::<usize, u64>(1)?),
+                        row.get::<usize, Option<u64>>(2)?,
+                    ))
+                },
+            )
+            .optional()
+    }
+
+    pub fn remove_join_request(&mut self, id: u64) -> Result<bool, Error> {
+        Ok(self
+            .db
+            .execute("DELETE FROM join_requests WHERE id = ?1", params![id])?
+            > 0)
+    }
+
+    /// Keeps a denied request around so the member cannot ask again straight away.
+    pub fn deny_join_request(&mut self, id: u64, now: u64) -> Result<bool, Error> {
+        Ok(self.db.execute(
+            "UPDATE join_requests SET denied_at = ?2 WHERE id = ?1 AND denied_at IS NULL",
+            params![id, now],
+        )? > 0)
+    }
+
+    /// Drops join requests nobody decided on in time and denials whose cooldown ended,
+    /// returns the amount of expired undecided requests.
+    pub fn purge_join_requests(&mut self, now: u64) -> Result<usize, Error> {
+        let expired = self.db.execute(
+            "DELETE FROM join_requests WHERE denied_at IS NULL AND timestamp + ?2 <= ?1",
+            params![now, JOIN_REQUEST_EXPIRY],
+        )?;
+        self.db.execute(
+            "DELETE FROM join_requests WHERE denied_at + ?2 <= ?1",
+            params![now, JOIN_REQUEST_COOLDOWN],
+        )?;
+        Ok(expired)
+    }
+
+    //ANCHOR list manager functions
+
+    pub fn add_list_manager(&mut self, list_id: ListId, user_id: UserId) -> bool {
+        self.db
+            .execute(
+                "INSERT OR IGNORE INTO list_managers (list_id, user_id) VALUES (?1, ?2)",
+                params![list_id, user_id.get()],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn remove_list_manager(&mut self, list_id: ListId, user_id: UserId) -> bool {
+        self.db
+            .execute(
+                "DELETE FROM list_managers WHERE list_id = ?1 AND user_id = ?2",
+                params![list_id, user_id.get()],
+            )
+            .unwrap()
+            > 0
+    }
+
+    pub fn get_list_managers(&mut self, list_id: ListId) -> Vec<UserId> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT user_id FROM list_managers WHERE list_id = ?1")
+            .unwrap();
+        let rows = stmt
+            .query_map(params![list_id], |row| {
+                row.get::<usize, u64>(0).map(UserId::new)
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<UserId>, _>>().unwrap()
+    }
+
+    pub fn is_list_manager(&self, list_id: ListId, user_id: UserId) -> bool {
+        self.db
+            .query_row(
+                "SELECT 1 FROM list_managers WHERE list_id = ?1 AND user_id = ?2",
+                params![list_id, user_id.get()],
+                |_| Ok(()),
+            )
+            .optional()
+            .unwrap()
+            .is_some()
+    }
+
+    /// The names of every list a user manages in a guild.
+    pub fn get_managed_list_names(&mut self, guild_id: GuildId, user_id: UserId) -> Vec<String> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT alias.name FROM alias, lists, list_managers WHERE alias.list_id = lists.id \
+                AND list_managers.list_id = lists.id AND lists.guild_id = ?1 AND list_managers.user_id = ?2 \
+                AND lists.archived_at IS NULL",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get(), user_id.get()], |row| {
+                row.get::<usize, String>(0)
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<String>, _>>().unwrap()
+    }
+
     //ANCHOR role functions
 
     pub fn get_role_permissions(
@@ -633,6 +2304,31 @@ impl Database {
         Ok(())
     }
 
+    pub fn get_role_quota(&mut self, guild_id: GuildId, role_id: RoleId) -> i64 {
+        self.ensure_role_present(guild_id, role_id).unwrap();
+        self.db
+            .query_row(
+                "SELECT ping_quota FROM role_settings WHERE role_id=?1 AND guild_id=?2",
+                params![role_id.get(), guild_id.get()],
+                |row| row.get::<usize, i64>(0),
+            )
+            .unwrap()
+    }
+
+    pub fn set_role_quota(
+        &mut self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        quota: i64,
+    ) -> Result<(), Error> {
+        self.ensure_role_present(guild_id, role_id)?;
+        self.db.execute(
+            "UPDATE role_settings SET ping_quota = ?1 WHERE role_id=?2 AND guild_id=?3",
+            params![quota, role_id.get(), guild_id.get()],
+        )?;
+        Ok(())
+    }
+
     //ANCHOR user functions
 
     pub fn get_user_permissions(
@@ -695,6 +2391,53 @@ impl Database {
             .expect("SQL statement malformed or SQL error");
     }
 
+    pub fn get_user_quota(&mut self, guild_id: GuildId, user_id: UserId) -> i64 {
+        self.ensure_user_present(guild_id, user_id);
+        self.db
+            .query_row(
+                "SELECT ping_quota FROM user_settings WHERE user_id=?1 AND guild_id=?2",
+                params![user_id.get(), guild_id.get()],
+                |row| row.get::<usize, i64>(0),
+            )
+            .unwrap()
+    }
+
+    pub fn set_user_quota(&mut self, guild_id: GuildId, user_id: UserId, quota: i64) -> bool {
+        self.ensure_user_present(guild_id, user_id);
+        self.db
+            .execute(
+                "UPDATE user_settings SET ping_quota = ?1 WHERE user_id=?2 AND guild_id=?3",
+                params![quota, user_id.get(), guild_id.get()],
+            )
+            .expect("SQL statement malformed or SQL error")
+            > 0
+    }
+
+    pub fn set_user_dm_pings(&mut self, guild_id: GuildId, user_id: UserId, enabled: bool) -> bool {
+        self.ensure_user_present(guild_id, user_id);
+        self.db
+            .execute(
+                "UPDATE user_settings SET dm_pings = ?1 WHERE user_id=?2 AND guild_id=?3",
+                params![enabled, user_id.get(), guild_id.get()],
+            )
+            .expect("SQL statement malformed or SQL error")
+            > 0
+    }
+
+    /// Members that asked not to receive pings by direct message.
+    pub fn get_dm_opt_outs(&mut self, guild_id: GuildId) -> BTreeSet<UserId> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT user_id FROM user_settings WHERE guild_id=?1 AND dm_pings = 0")
+            .unwrap();
+        let rows = stmt
+            .query_map(params![guild_id.get()], |row| {
+                row.get::<usize, u64>(0).map(UserId::new)
+            })
+            .unwrap();
+        rows.collect::<Result<BTreeSet<UserId>, _>>().unwrap()
+    }
+
     //ANCHOR channel functions
 
     fn ensure_channel_present(&mut self, channel_id: ChannelId) -> () {
@@ -899,6 +2642,10 @@ impl Database {
                         SELECT COUNT(memberships.user_id) \
                         FROM memberships \
                         WHERE memberships.list_id = lists.id \
+                        AND NOT EXISTS ( \
+                            SELECT 1 FROM departures \
+                            WHERE departures.user_id = memberships.user_id \
+                            AND departures.guild_id = lists.guild_id) \
                     )\
                     FROM proposals \
                     INNER JOIN lists ON proposals.list_id=lists.id \
@@ -928,6 +2675,10 @@ impl Database {
                         SELECT COUNT(memberships.user_id) \
                         FROM memberships \
                         WHERE memberships.list_id = lists.id \
+                        AND NOT EXISTS ( \
+                            SELECT 1 FROM departures \
+                            WHERE departures.user_id = memberships.user_id \
+                            AND departures.guild_id = lists.guild_id) \
                     )\
                     FROM proposals \
                     INNER JOIN lists ON proposals.list_id=lists.id";
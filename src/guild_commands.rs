@@ -1,5 +1,7 @@
 use serenity::{
-    all::{Command, CommandOptionType, CommandType, CreateCommand, CreateCommandOption},
+    all::{
+        ChannelType, Command, CommandOptionType, CommandType, CreateCommand, CreateCommandOption,
+    },
     model::{id::GuildId, permissions},
     prelude::*,
 };
@@ -11,12 +13,12 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
         &ctx.http,
         vec![
             CreateCommand::new("ping")
-            .description("Pings all given lists")
+            .description("Pings all given lists, combine lists with & (both), | (either) and - (except)")
             .add_option(
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "list",
-                    "A single pinglist",
+                    "A pinglist, or an expression such as a & (b | c) - d",
                 )
                 .required(true)
                 .set_autocomplete(true),
@@ -25,7 +27,7 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "l2",
-                    "A single pinglist",
+                    "A pinglist, or an expression such as a & (b | c) - d",
                 )
                 .required(false)
                 .set_autocomplete(true),
@@ -34,7 +36,7 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "l3",
-                    "A single pinglist",
+                    "A pinglist, or an expression such as a & (b | c) - d",
                 )
                 .required(false)
                 .set_autocomplete(true),
@@ -43,7 +45,7 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "l4",
-                    "A single pinglist",
+                    "A pinglist, or an expression such as a & (b | c) - d",
                 )
                 .required(false)
                 .set_autocomplete(true),
@@ -52,12 +54,29 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "l5",
-                    "A single pinglist",
+                    "A pinglist, or an expression such as a & (b | c) - d",
                 )
                 .required(false)
                 .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "message",
+                    "A short message explaining the ping",
+                )
+                .required(false)
+                .max_length(1000),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "long_message",
+                    "Write a longer message in a form before pinging",
+                )
+                .required(false),
             ),
-            CreateCommand::new("ping with context").kind(CommandType::Message),
+            CreateCommand::new("Ping list").kind(CommandType::Message),
             CreateCommand::new("create")
             .description("Adds a list")
             .default_member_permissions(can_manage_messages)
@@ -81,6 +100,18 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 .set_autocomplete(true)
                 .required(true),
             ),
+            CreateCommand::new("restore")
+            .description("Restores a removed list")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name",
+                    "the name of the archived list",
+                )
+                .set_autocomplete(true)
+                .required(true),
+            ),
             CreateCommand::new("join")
             .description("Join all given lists")
             .add_option(
@@ -127,6 +158,18 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 )
                 .required(false)
                 .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "duration",
+                    "Leave these lists again after a while",
+                )
+                .required(false)
+                .add_int_choice("1 day", 86400)
+                .add_int_choice("1 week", 604800)
+                .add_int_choice("30 days", 2592000)
+                .add_int_choice("90 days", 7776000),
             ),
             CreateCommand::new("leave")
             .description("Leave all given lists")
@@ -175,7 +218,18 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 .required(false)
                 .set_autocomplete(true),
             ),
-            CreateCommand::new("get").description("Get all lists you're subscribed to"),
+            CreateCommand::new("get").description("Browse and leave the lists you're subscribed to"),
+            CreateCommand::new("info")
+            .description("Show everything about a list")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "list",
+                    "The list to show",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            ),
             CreateCommand::new("list")
             .description("List all lists")
             .add_option(
@@ -192,11 +246,66 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                     "filter",
                     "Require this to be present in the name or description of a list.",
                 )
-                .required(false),
+                .required(false)
+                .max_length(75),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "category",
+                    "Only show the lists in this category",
+                )
+                .required(false)
+                .max_length(50)
+                .set_autocomplete(true),
+            ),
+            CreateCommand::new("category")
+            .description("Group lists into categories")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "create", "Create a category")
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "name", "The name of the category")
+                            .required(true)
+                            .max_length(50),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "rename", "Rename a category")
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "name", "The current name of the category")
+                            .required(true)
+                            .set_autocomplete(true),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "new_name", "The new name of the category")
+                            .required(true)
+                            .max_length(50),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "Delete a category, its lists are kept")
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "name", "The category to delete")
+                            .required(true)
+                            .set_autocomplete(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "assign", "Put a list in a category")
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "list", "The list to move")
+                            .required(true)
+                            .set_autocomplete(true),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "category", "Leave out to remove the list from its category")
+                            .required(false)
+                            .set_autocomplete(true),
+                    ),
             ),
             CreateCommand::new("alias")
             .description("Add more names to a list")
-            .default_member_permissions(can_manage_messages)
             .add_option(
                 CreateCommandOption::new(
                     CommandOptionType::String,
@@ -214,6 +323,63 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 )
                 .required(true),
             ),
+            CreateCommand::new("link_role")
+            .description("Keep a list in sync with a role, leave out the role to unlink")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "list", "The list to link")
+                    .required(true)
+                    .set_autocomplete(true),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Role,
+                "role",
+                "The role holders of which are the list members",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "prefer",
+                    "What to do with members that only have the role or only are on the list",
+                )
+                .add_string_choice("merge both", "merge")
+                .add_string_choice("the role", "role")
+                .add_string_choice("the list", "list"),
+            ),
+            CreateCommand::new("list_managers")
+            .description("Let members administer a list without being a moderator")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "list", "The managed list")
+                    .required(true)
+                    .set_autocomplete(true),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "add",
+                "Make this member a manager of the list",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "remove",
+                "Stop this member from managing the list",
+            )),
+            CreateCommand::new("include")
+            .description("Make a list contain everyone on other lists")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "list", "The including list")
+                    .required(true)
+                    .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "add", "Include the members of this list")
+                    .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "remove", "Stop including this list")
+                    .set_autocomplete(true),
+            ),
             CreateCommand::new("propose")
             .description("Propose a list")
             .add_option(
@@ -225,6 +391,85 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 .required(true),
             ),
             CreateCommand::new("list_proposals").description("See proposed lists"),
+            CreateCommand::new("schedule_ping")
+            .description("Ping lists at a later time, once or repeatedly")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "lists",
+                    "A pinglist, or an expression such as a & (b | c) - d",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "time",
+                    "When to ping in UTC, as YYYY-MM-DD HH:MM",
+                )
+                .required(false),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "repeat",
+                    "How often to repeat the ping",
+                )
+                .required(false)
+                .add_int_choice("Once", 0)
+                .add_int_choice("Daily", 86400)
+                .add_int_choice("Weekly", 604800),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "schedule",
+                    "Repeat on a cron schedule in UTC instead, such as 0 18 * * fri",
+                )
+                .required(false)
+                .max_length(100),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "Where to ping, defaults to this channel",
+                )
+                .required(false)
+                .channel_types(vec![ChannelType::Text, ChannelType::News]),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "note",
+                    "A message to show with the ping",
+                )
+                .required(false)
+                .max_length(1000),
+            ),
+            CreateCommand::new("dm_pings")
+            .description("Choose whether lists may ping you by direct message")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "enabled",
+                    "Turn off to be mentioned in the channel instead",
+                )
+                .required(true),
+            ),
+            CreateCommand::new("list_scheduled").description("See all scheduled pings"),
+            CreateCommand::new("cancel_scheduled")
+            .description("Cancel a scheduled ping")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "id",
+                    "The number of the scheduled ping, see /list_scheduled",
+                )
+                .required(true)
+                .min_int_value(1),
+            ),
             CreateCommand::new("Cancel proposal")
                 .default_member_permissions(can_manage_messages)
                 .kind(CommandType::Message),
@@ -233,7 +478,6 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 .kind(CommandType::Message),
             CreateCommand::new("add")
                 .description("Add a user to lists")
-                .default_member_permissions(can_manage_messages)
                 .add_option(
                     CreateCommandOption::new(
                         CommandOptionType::User,
@@ -286,10 +530,21 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                     )
                     .required(false)
                     .set_autocomplete(true),
+                )
+                .add_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "duration",
+                        "Remove the user from these lists again after a while",
+                    )
+                    .required(false)
+                    .add_int_choice("1 day", 86400)
+                    .add_int_choice("1 week", 604800)
+                    .add_int_choice("30 days", 2592000)
+                    .add_int_choice("90 days", 7776000),
                 ),
             CreateCommand::new("kick")
             .description("Kick a user from lists")
-            .default_member_permissions(can_manage_messages)
             .add_option(
                 CreateCommandOption::new(
                     CommandOptionType::User,
@@ -345,7 +600,6 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             ),
             CreateCommand::new("remove_alias")
             .description("Removes a list alias")
-            .default_member_permissions(can_manage_messages)
             .add_option(
                 CreateCommandOption::new(
                     CommandOptionType::String,
@@ -355,6 +609,74 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 .required(true)
                 .set_autocomplete(true),
             ),
+            CreateCommand::new("rename")
+            .description("Renames a list")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name",
+                    "The name to change",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "new_name",
+                    "The new name of the list",
+                )
+                .required(true),
+            ),
+            CreateCommand::new("merge")
+            .description("Moves the members and names of a list into another list")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "list",
+                    "The list to merge, it is removed afterwards",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "into",
+                    "The list that receives the members and names",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            ),
+            CreateCommand::new("split")
+            .description("Moves some members of a list to a new list")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "list",
+                    "The list to split",
+                )
+                .required(true)
+                .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name",
+                    "The name of the new list",
+                )
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Role,
+                    "role",
+                    "Move every member of the list with this role, instead of picking them",
+                )
+                .required(false),
+            ),
             CreateCommand::new("log_purge")
             .description("Log and purge a complicated sequence of messages")
             .default_member_permissions(can_manage_messages)
@@ -366,6 +688,51 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
                 )
                 .required(false),
             ),
+            CreateCommand::new("ping_history")
+            .description("See who pinged which lists and when")
+            .default_member_permissions(can_manage_messages)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "list",
+                    "Only show pings of this list",
+                )
+                .required(false)
+                .set_autocomplete(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Only show pings by this user",
+                )
+                .required(false),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "from",
+                    "Only show pings since this UTC time, as YYYY-MM-DD or YYYY-MM-DD HH:MM",
+                )
+                .required(false),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "to",
+                    "Only show pings before this UTC time, as YYYY-MM-DD or YYYY-MM-DD HH:MM",
+                )
+                .required(false),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "page",
+                    "What part of the history you want to see",
+                )
+                .required(false)
+                .min_int_value(1),
+            ),
             CreateCommand::new("list_auto_responses")
                 .description("Show all current automatic responses.")
                 .default_member_permissions(can_manage_messages),
@@ -633,7 +1000,6 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             ),
             CreateCommand::new("configure")
             .description("Houses various configuration subcommands")
-            .default_member_permissions(can_manage_messages)
             .add_option(
                 CreateCommandOption::new(CommandOptionType::SubCommand, "show", "Shows all the current settings in a neat embed.")
             )
@@ -647,6 +1013,37 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
         .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "set_list_ping_cooldown", "Set the cooldown between seperate pings to the same list in seconds.")
             .required(false)
         )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "departure_grace_period", "Remove members from all lists after they left the server for this many seconds.")
+            .required(false)
+            .min_int_value(0)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "ping_quota", "How many pings a member may send per quota window, 0 for unlimited.")
+            .required(false)
+            .min_int_value(0)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "confirm_threshold", "Ask for confirmation before pinging more than this many members, 0 to never ask.")
+            .required(false)
+            .min_int_value(0)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "quota_window", "The rolling window pings are counted in for the quota.")
+            .required(false)
+            .add_int_choice("Hour", 3600)
+            .add_int_choice("Day", 86400)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Channel, "approval_channel", "Post requests to join lists that need approval in this channel.")
+            .required(false)
+            .channel_types(vec![ChannelType::Text])
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "expiry_notice", "Tell members by direct message when a temporary membership ends.")
+            .required(false)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "archive_retention", "Delete removed lists for good after this many seconds.")
+            .required(false)
+            .min_int_value(0)
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "approval_by_dm", "Send requests to join lists to the list managers instead of the approval channel.")
+            .required(false)
+        )
         )
         .add_option(
             CreateCommandOption::new(CommandOptionType::SubCommand, "role", "Settings that affect a specific role")
@@ -670,6 +1067,10 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             CreateCommandOption::new(CommandOptionType::Boolean, "exclude_from_cooldown", "Toggle whether or not people with this role can ignore the guild-wide cooldown.")
             .required(false)
         )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "ping_quota", "Override the ping quota for this role, 0 for unlimited and -1 to reset.")
+            .required(false)
+        )
         )
         .add_option(
             CreateCommandOption::new(CommandOptionType::SubCommand, "user", "Settings that affect a specific user")
@@ -691,6 +1092,9 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "exclude_from_cooldown", "Toggle whether or not people with this user can ignore the guild-wide cooldown.")
                 .required(false)
             )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "ping_quota", "Override the ping quota for this user, 0 for unlimited and -1 to reset.")
+                .required(false)
+            )
         )
         .add_option(
             CreateCommandOption::new(CommandOptionType::SubCommand, "list", "All settings that affect a single pinglist")
@@ -703,6 +1107,8 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             )
             .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "cooldown", "Override the list cooldown.")
                 .required(false)
+                // min_int_value only takes unsigned values
+                .min_number_value(-1.0)
             )
             .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "allow_join", "Allow anyone to join or leave this list.")
                 .required(false)
@@ -719,6 +1125,39 @@ pub async fn add_all_application_commands(gid: &mut GuildId, ctx: &Context) -> V
             .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "show", "Hide this list from /list.")
                 .required(false)
             )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "delivery", "How members are reached when this list is pinged.")
+                .required(false)
+                .add_string_choice("Channel mentions", "0")
+                .add_string_choice("Direct messages", "1")
+                .add_string_choice("Both", "2")
+            )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "require_approval", "Members ask to join and a moderator or list manager approves.")
+                .required(false)
+            )
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "category", "Apply list settings to every list in a category")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "category", "The category to configure.")
+                .required(true)
+                .set_autocomplete(true)
+            )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "cooldown", "Override the cooldown of these lists.")
+                .required(false)
+                // min_int_value only takes unsigned values
+                .min_number_value(-1.0)
+            )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "allow_join", "Allow anyone to join or leave these lists.")
+                .required(false)
+                .add_string_choice("Reset", "0")
+                .add_string_choice("Deny", "1")
+                .add_string_choice("Allow", "2")
+            )
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "allow_ping", "Allow anyone to mention these lists.")
+                .required(false)
+                .add_string_choice("Reset", "0")
+                .add_string_choice("Deny", "1")
+                .add_string_choice("Allow", "2")
+            )
         )
         .add_option(
             CreateCommandOption::new(CommandOptionType::SubCommand, "channel", "Allows you to configure channels.")
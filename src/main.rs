@@ -1,12 +1,13 @@
 use serenity::{
     all::{
         ActionRow, ActionRowComponent, ApplicationId, Button, ButtonKind, ButtonStyle,
-        CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
-        ComponentInteraction, ComponentInteractionDataKind, CreateAutocompleteResponse,
-        CreateEmbedAuthor, CreateInputText, CreateInteractionResponse,
-        CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
-        CreateModal, EditInteractionResponse, EditMessage, EmbedAuthor, GetMessages,
-        InputTextStyle, Interaction,
+        ChunkGuildFilter, CommandDataOption, CommandDataOptionValue, CommandInteraction,
+        CommandOptionType, ComponentInteraction, ComponentInteractionDataKind,
+        CreateAllowedMentions, CreateAutocompleteResponse, CreateEmbedAuthor, CreateInputText,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, CreateModal, EditInteractionResponse,
+        EditMessage, EmbedAuthor, GetMessages, Guild, GuildMembersChunkEvent, InputTextStyle,
+        Interaction, ModalInteraction,
     },
     async_trait,
     builder::{
@@ -25,7 +26,7 @@ use serenity::{
 
 use std::{
     cmp::min,
-    collections::BTreeSet,
+    collections::{BTreeSet, HashMap},
     env,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -37,7 +38,10 @@ use std::{
 use dotenv::dotenv;
 
 mod structures;
-use structures::{JoinResult, ListId, ProposalStatus, LOGCONDITION, LOGTRIGGER, PERMISSION};
+use structures::{
+    DeliveryMode, GuildRoster, JoinAuthority, JoinResult, ListId, PingHistoryFilter, PingRecord,
+    ProposalStatus, ScheduledPing, LOGCONDITION, LOGTRIGGER, PERMISSION,
+};
 
 mod guild_commands;
 
@@ -46,11 +50,27 @@ use database::Database;
 
 mod pickle_import;
 
+mod ping_query;
+use ping_query::PingQuery;
+
+mod alias_index;
+use alias_index::{AliasIndex, IndexedAlias};
+
+mod recurrence;
+use recurrence::Recurrence;
+
 struct DB;
 struct BotData {
     database: Arc<Mutex<Database>>,
-    global: std::collections::HashMap<GuildId, u64>,
-    local: std::collections::HashMap<ListId, u64>,
+    rosters: HashMap<GuildId, GuildRoster>,
+    /// Pings waiting for a message, confirmation or to be queued, by interaction id, with the
+    /// time they were stored.
+    pending_pings: HashMap<u64, (PingRequest, u64)>,
+    /// Splits waiting for their members to be picked, by interaction id, with the time they
+    /// were stored.
+    pending_splits: HashMap<u64, (ListId, String, u64)>,
+    /// List names of each guild for autocomplete, rebuilt when the database has newer names.
+    alias_indexes: HashMap<GuildId, AliasIndex>,
 }
 
 impl TypeMapKey for DB {
@@ -58,13 +78,56 @@ impl TypeMapKey for DB {
 }
 
 enum ListInvalidReasons {
-    OnLocalCooldown,
-    OnGlobalCooldown,
+    /// The list can be pinged again from the contained timestamp on.
+    OnLocalCooldown(u64),
+    /// The guild can be pinged again from the contained timestamp on.
+    OnGlobalCooldown(u64),
     GuildRestrictPing,
     ChannelRestrictPing,
     ListRestrictPing,
     DoesNotExist,
     RoleRestrictPing,
+    MalformedQuery(String),
+    MembersUnavailable,
+    MessageTooLong,
+    /// The user sent too many pings recently, the oldest one stops counting at this timestamp.
+    QuotaExceeded(u64),
+}
+
+/// Everything needed to check and send a ping, independent of how it was requested.
+struct PingRequest {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+    roles: Vec<RoleId>,
+    as_admin: bool,
+    queries: Vec<String>,
+    message: Option<String>,
+}
+
+const PING_MESSAGE_MAX_LENGTH: usize = 2000;
+/// Seconds after which interactions nobody acted on are forgotten.
+const PENDING_TIMEOUT: u64 = 900;
+
+/// A ping that passed all checks, with the members that remain in the guild.
+struct PreparedPing {
+    list_ids: Vec<ListId>,
+    members: Vec<UserId>,
+    timestamp: u64,
+    delivery: DeliveryMode,
+    /// Members that do not want to be reached by direct message, only filled when needed.
+    dm_opt_outs: BTreeSet<UserId>,
+    /// The cooldowns the ping was checked against as name, list id (0 for the guild), last ping
+    /// and length, so `commit_ping` can tell whether another ping started them in between.
+    checked_cooldowns: Vec<(String, ListId, u64, u64)>,
+}
+
+/// Where the messages of a ping go, as a response to a command or straight into a channel.
+#[derive(Clone, Copy)]
+enum PingOrigin<'a> {
+    Command(&'a CommandInteraction),
+    /// Optionally replying to the given message.
+    Channel(Option<MessageId>),
 }
 
 /*
@@ -92,6 +155,25 @@ impl Handler {
             .contains(Permissions::MANAGE_MESSAGES);
     }
 
+    /// Moderators can administer every list, list managers only the lists assigned to them.
+    async fn can_manage_list(command: &CommandInteraction, ctx: &Context, list_name: &str) -> bool {
+        if Handler::can_manage_messages(command) {
+            return true;
+        }
+        let Some(guild_id) = command.guild_id else {
+            return false;
+        };
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        let manages = db.lock().ok().is_some_and(|mut x| {
+            x.get_list_id_by_name(list_name, guild_id)
+                .is_some_and(|list_id| x.is_list_manager(list_id, command.user.id))
+        });
+        manages
+    }
+
     async fn send_channel(
         text: &str,
         channel_id: ChannelId,
@@ -128,23 +210,6 @@ impl Handler {
             .await
             .expect("Failed to send text response, see error for details.");
     }
-    async fn send_followup(
-        text: &str,
-        command: &CommandInteraction,
-        ctx: &Context,
-        ephemeral: bool,
-    ) {
-        // Can fail if message is too long.
-        command
-            .create_followup(
-                &ctx.http,
-                CreateInteractionResponseFollowup::new()
-                    .content(text)
-                    .ephemeral(ephemeral),
-            )
-            .await
-            .expect("Failed to send text response, see error for details.");
-    }
 
     async fn send_not_allowed(command: &CommandInteraction, ctx: &Context) {
         Handler::send_text(
@@ -160,26 +225,35 @@ impl Handler {
         Handler::send_text("This command must be used in a server.", command, ctx, true).await;
     }
 
-    async fn handle_ping(&self, command: &CommandInteraction, ctx: &Context) {
-        let Some(guild_id) = command.guild_id else {
-            Handler::send_not_in_guild(command, ctx).await;
-            return;
-        };
-        let channel_id = command.channel_id;
-        let member = command.member.as_ref().unwrap();
-        let member_admin = Handler::can_manage_messages(command);
-        let role_ids: &Vec<RoleId> = &member.roles;
+    /// Runs all permission and cooldown checks of a ping and resolves the members to mention.
+    async fn prepare_ping(
+        ctx: &Context,
+        request: &PingRequest,
+    ) -> Result<PreparedPing, Vec<(String, ListInvalidReasons)>> {
+        let guild_id = request.guild_id;
+        let member_admin = request.as_admin;
 
-        let list_names: Vec<CommandDataOption> = command.data.options.clone();
         let mut list_ids: Vec<ListId> = vec![];
         let mut members: BTreeSet<UserId> = BTreeSet::new();
+        let mut list_members: HashMap<String, BTreeSet<UserId>> = HashMap::new();
         let mut invalid_lists: Vec<(String, ListInvalidReasons)> = vec![];
+        let mut delivery: Option<DeliveryMode> = None;
+        let mut dm_opt_outs: BTreeSet<UserId> = BTreeSet::new();
+        let mut checked_cooldowns: Vec<(String, ListId, u64, u64)> = vec![];
+
+        if request
+            .message
+            .as_ref()
+            .is_some_and(|message| message.chars().count() > PING_MESSAGE_MAX_LENGTH)
+        {
+            invalid_lists.push(("all".to_string(), ListInvalidReasons::MessageTooLong));
+        }
 
         let mut data = ctx.data.write().await;
         let BotData {
             database: db,
-            global,
-            local,
+            rosters,
+            ..
         } = data
             .get_mut::<DB>()
             .expect("Could not find database in bot data");
@@ -193,11 +267,11 @@ impl Handler {
             let mut ignore_cooldown = member_admin;
 
             let (_, user_canping, user_ignore_cooldown) =
-                x.get_user_permissions(guild_id, member.user.id);
+                x.get_user_permissions(guild_id, request.user_id);
             ignore_cooldown = ignore_cooldown || user_ignore_cooldown;
             override_canping = override_canping.combine(user_canping);
 
-            for role_id in role_ids {
+            for role_id in &request.roles {
                 let (_, role_canping, role_ignore_cooldown) =
                     x.get_role_permissions(guild_id, *role_id);
                 override_canping = override_canping.combine(role_canping);
@@ -205,7 +279,7 @@ impl Handler {
             }
 
             let (general_cooldown, general_canping, pingcooldown) = x.get_guild_ping_data(guild_id);
-            let (_, channel_ping_rule, _) = x.get_channel_permissions(guild_id, channel_id);
+            let (_, channel_ping_rule, _) = x.get_channel_permissions(guild_id, request.channel_id);
 
             if override_canping == PERMISSION::DENY {
                 invalid_lists.push(("all".to_string(), ListInvalidReasons::RoleRestrictPing));
@@ -218,20 +292,70 @@ impl Handler {
                 }
             }
 
-            let last_global = global.entry(guild_id).or_insert(0);
+            if !member_admin {
+                // The user setting wins, otherwise the most generous role, otherwise the guild.
+                let (guild_quota, quota_window) = x.get_guild_quota(guild_id);
+                let mut quota = x.get_user_quota(guild_id, request.user_id);
+                if quota == -1 {
+                    for role_id in &request.roles {
+                        match x.get_role_quota(guild_id, *role_id) {
+                            -1 => (),
+                            0 => quota = 0,
+                            role_quota if quota != 0 => quota = quota.max(role_quota),
+                            _ => (),
+                        }
+                    }
+                }
+                if quota == -1 {
+                    quota = guild_quota as i64;
+                }
+                let (sent, oldest) = x.get_user_pings_since(
+                    guild_id,
+                    request.user_id,
+                    timestamp.saturating_sub(quota_window),
+                );
+                if quota > 0 && sent >= quota as usize {
+                    invalid_lists.push((
+                        "all".to_string(),
+                        ListInvalidReasons::QuotaExceeded(oldest + quota_window),
+                    ));
+                }
+            }
+
+            let last_global = x.get_guild_last_ping(guild_id);
+
+            if !ignore_cooldown && last_global + general_cooldown >= timestamp {
+                invalid_lists.push((
+                    "all".to_string(),
+                    ListInvalidReasons::OnGlobalCooldown(last_global + general_cooldown + 1),
+                ));
+            } else if !ignore_cooldown {
+                checked_cooldowns.push(("all".to_string(), 0, last_global, general_cooldown));
+            }
 
-            if !ignore_cooldown && *last_global + general_cooldown >= timestamp {
-                invalid_lists.push(("all".to_string(), ListInvalidReasons::OnGlobalCooldown));
+            let mut queries: Vec<PingQuery> = vec![];
+            for query in &request.queries {
+                // A full list name always wins over reading it as an expression.
+                if x.get_list_id_by_name(query, guild_id).is_some() {
+                    queries.push(PingQuery::List(query.to_string()));
+                    continue;
+                }
+                match PingQuery::parse(query) {
+                    Ok(parsed) => queries.push(parsed),
+                    Err(reason) => invalid_lists.push((
+                        query.to_string(),
+                        ListInvalidReasons::MalformedQuery(reason),
+                    )),
+                }
             }
 
-            for list_name in &list_names {
-                let list_name = list_name
-                    .value
-                    .as_str()
-                    .expect("Invalid /ping definition, should be string type");
+            for list_name in queries.iter().flat_map(PingQuery::list_names) {
+                if list_members.contains_key(list_name) {
+                    continue;
+                }
 
                 if let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) {
-                    let last_time = local.entry(list_id).or_insert(0);
+                    let last_time = x.get_list_last_ping(list_id);
                     let (mut list_cooldown, _, list_ping_permission) =
                         x.get_list_permissions(list_id);
                     if list_cooldown == -1 {
@@ -244,796 +368,3668 @@ impl Handler {
                         continue;
                     }
 
-                    if !member_admin && *last_time + (list_cooldown as u64) >= timestamp {
-                        invalid_lists
-                            .push((list_name.to_string(), ListInvalidReasons::OnLocalCooldown));
+                    if !member_admin && last_time + (list_cooldown as u64) >= timestamp {
+                        invalid_lists.push((
+                            list_name.to_string(),
+                            ListInvalidReasons::OnLocalCooldown(
+                                last_time + list_cooldown as u64 + 1,
+                            ),
+                        ));
                         continue;
                     }
+                    if !member_admin {
+                        checked_cooldowns.push((
+                            list_name.to_string(),
+                            list_id,
+                            last_time,
+                            list_cooldown as u64,
+                        ));
+                    }
 
-                    members.extend(x.get_members_in_list(list_id));
-                    list_ids.push(list_id);
+                    list_members.insert(
+                        list_name.to_string(),
+                        BTreeSet::from_iter(x.get_effective_members_in_list(list_id)),
+                    );
+                    if !list_ids.contains(&list_id) {
+                        list_ids.push(list_id);
+                        let list_delivery = x.get_delivery_mode(list_id);
+                        delivery = Some(
+                            delivery.map_or(list_delivery, |mode| mode.combine(list_delivery)),
+                        );
+                    }
                 } else {
                     invalid_lists.push((list_name.to_string(), ListInvalidReasons::DoesNotExist));
                 }
             }
+
+            if invalid_lists.is_empty() {
+                for query in &queries {
+                    members.extend(query.evaluate(&list_members));
+                }
+                if delivery.is_some_and(|mode| mode != DeliveryMode::Channel) {
+                    dm_opt_outs = x.get_dm_opt_outs(guild_id);
+                }
+            }
         }
 
-        // I hate this, but it should work well enough...
-        let all_ids = guild_id
-            .members_iter(&ctx.http)
-            .map_ok(|m| m.user.id)
-            .map(Result::ok)
-            .collect::<Vec<Option<UserId>>>()
-            .await;
-        if !all_ids.iter().all(Option::is_some) {
-            Handler::send_text(
-                "A problem occured retrieving guild members, try again later.",
-                command,
-                ctx,
-                true,
-            )
-            .await;
-            return;
+        if !invalid_lists.is_empty() {
+            return Err(invalid_lists);
         }
-        let present_ids: BTreeSet<UserId> = BTreeSet::from_iter(
-            all_ids
-                .into_iter()
-                .map(Option::unwrap)
-                .collect::<Vec<UserId>>(),
-        );
 
-        let members: Vec<&UserId> = members.intersection(&present_ids).collect();
+        let members: Vec<UserId> = match rosters.get(&guild_id) {
+            Some(roster) if roster.complete => {
+                members.intersection(&roster.members).cloned().collect()
+            }
+            _ => {
+                // The roster has not been filled by the gateway yet, fetch everyone once.
+                let all_ids = guild_id
+                    .members_iter(&ctx.http)
+                    .map_ok(|m| m.user.id)
+                    .map(Result::ok)
+                    .collect::<Vec<Option<UserId>>>()
+                    .await;
+                if !all_ids.iter().all(Option::is_some) {
+                    return Err(vec![(
+                        "all".to_string(),
+                        ListInvalidReasons::MembersUnavailable,
+                    )]);
+                }
+                let present_ids: BTreeSet<UserId> =
+                    BTreeSet::from_iter(all_ids.into_iter().map(Option::unwrap));
+                let members = members.intersection(&present_ids).cloned().collect();
+                Handler::reconcile_departures(db, guild_id, &present_ids);
+                rosters.insert(
+                    guild_id,
+                    GuildRoster {
+                        members: present_ids,
+                        complete: true,
+                    },
+                );
+                members
+            }
+        };
 
-        let mut first_message = true;
+        Ok(PreparedPing {
+            list_ids,
+            members,
+            timestamp,
+            delivery: delivery.unwrap_or(DeliveryMode::Channel),
+            dm_opt_outs,
+            checked_cooldowns,
+        })
+    }
 
-        let mut ephemeral = false;
-        let mut content = String::new();
-        if invalid_lists.len() == 0 {
-            global.insert(guild_id, timestamp);
-            for list_id in list_ids {
-                local.insert(list_id, timestamp);
-            }
-
-            if members.len() > 0 {
-                content = format!("Mentioning ");
-                let mut i = 0;
-                for list_name in &list_names {
-                    let list_name = list_name
-                        .value
-                        .as_str()
-                        .expect("Invalid /ping definition, should be string type");
-                    content += list_name;
-                    i += 1;
-                    if i < list_names.len() {
-                        content += ", ";
-                    }
-                }
-                content += format!(" with {} members:\n", members.len()).as_str();
-                for member in members {
-                    content += format!("<@{}>, ", member).as_str();
-                    if content.len() > 1940 {
-                        if first_message {
-                            Handler::send_text(&content, command, ctx, false).await;
-                            first_message = false;
-                        } else {
-                            Handler::send_followup(&content, command, ctx, false).await;
-                        }
-                        content.clear();
-                    }
+    /// Starts the cooldowns for a ping that passed `prepare_ping` and is about to be sent. Fails
+    /// if another ping started one of the checked cooldowns since then.
+    async fn commit_ping(
+        ctx: &Context,
+        request: &PingRequest,
+        prepared: &PreparedPing,
+    ) -> Result<(), Vec<(String, ListInvalidReasons)>> {
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        let mut invalid_lists = vec![];
+        if let Ok(mut x) = db.clone().lock() {
+            for (name, list_id, last_ping, cooldown) in &prepared.checked_cooldowns {
+                let current = match list_id {
+                    0 => x.get_guild_last_ping(request.guild_id),
+                    _ => x.get_list_last_ping(*list_id),
+                };
+                if current != *last_ping && current + cooldown >= prepared.timestamp {
+                    invalid_lists.push((
+                        name.clone(),
+                        match list_id {
+                            0 => ListInvalidReasons::OnGlobalCooldown(current + cooldown + 1),
+                            _ => ListInvalidReasons::OnLocalCooldown(current + cooldown + 1),
+                        },
+                    ));
                 }
-            } else {
-                content += "These lists are empty.";
             }
-        } else {
-            ephemeral = true;
-            for falselist in invalid_lists {
-                content += match falselist.1 {
-                    ListInvalidReasons::ChannelRestrictPing => {
-                        format!("\nPings are not allowed in this channel.")
-                    }
-                    ListInvalidReasons::DoesNotExist => {
-                        format!("\nThe list {} does not exist.", falselist.0)
-                    }
-                    ListInvalidReasons::GuildRestrictPing => {
-                        format!("\nYou do not have permission to ping in this server.")
-                    }
-                    ListInvalidReasons::ListRestrictPing => {
-                        format!("\nThe list {} cannot be pinged.", falselist.0)
-                    }
-                    ListInvalidReasons::OnGlobalCooldown => {
-                        format!("\nAnother ping has happed recently, please try again later.")
-                    }
-                    ListInvalidReasons::OnLocalCooldown => {
-                        format!("\nThe list {} has been pinged recently, please try again later or exclude this list.", falselist.0)
-                    }
-                    ListInvalidReasons::RoleRestrictPing => {
-                        format!("One of your roles prevents you from using the ping command.\n")
-                    }
-                }.as_str()
+            if invalid_lists.is_empty() {
+                x.set_last_ping(request.guild_id, &prepared.list_ids, prepared.timestamp)
+                    .expect("Failed to store ping cooldowns");
             }
         }
-        if first_message {
-            Handler::send_text(&content, command, ctx, ephemeral).await;
+        if invalid_lists.is_empty() {
+            Ok(())
         } else {
-            Handler::send_followup(&content, command, ctx, false).await;
+            Err(invalid_lists)
         }
     }
 
-    async fn autocomplete_ping(&self, autocomplete: &CommandInteraction, ctx: &Context) {
-        let Some(guild_id) = autocomplete.guild_id else {
-            autocomplete
-                .create_response(
-                    &ctx.http,
-                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
-                )
-                .await
-                .expect("Failure communicating with discord api");
-            return;
-        };
-        const SUGGESTIONS: usize = 5;
-        let member = autocomplete.member.as_ref().unwrap();
-        let member_admin = member
-            .permissions
-            .expect("member reference did not originate from interaction")
-            .contains(Permissions::MANAGE_MESSAGES);
-
-        let mut filter = "";
-        for field in &autocomplete.data.options {
-            if let CommandDataOptionValue::Autocomplete { kind: _, ref value } = field.value {
-                filter = value;
-            }
-        }
-        let mut aliases: Vec<String> = Vec::new();
-
-        let mut data = ctx.data.write().await;
+    /// Keeps a record of a delivered ping for /ping_history.
+    async fn record_ping(
+        ctx: &Context,
+        request: &PingRequest,
+        prepared: &PreparedPing,
+        message_ids: Vec<MessageId>,
+    ) {
+        let data = ctx.data.read().await;
         let BotData { database: db, .. } = data
-            .get_mut::<DB>()
+            .get::<DB>()
             .expect("Could not find database in bot data");
-
         if let Ok(mut x) = db.clone().lock() {
-            aliases = x.get_list_aliases_by_search(guild_id, 0, SUGGESTIONS, filter, member_admin)
+            x.add_ping_history(&PingRecord {
+                id: 0,
+                guild_id: request.guild_id,
+                channel_id: request.channel_id,
+                user_id: request.user_id,
+                list_ids: prepared.list_ids.clone(),
+                member_count: prepared.members.len(),
+                timestamp: prepared.timestamp,
+                message_ids,
+            })
+            .expect("Failed to store ping history");
         }
+    }
 
-        let mut resp = CreateAutocompleteResponse::new();
-        for list in aliases {
-            resp = resp.add_string_choice(&list, &list);
+    /// Splits the mentions of a ping into messages that fit within discord's limits.
+    fn compose_ping(header: String, members: &[UserId]) -> Vec<String> {
+        let mut messages = vec![];
+        let mut content = header;
+        for member in members {
+            content += format!("<@{}>, ", member).as_str();
+            if content.len() > 1940 {
+                messages.push(content.clone());
+                content.clear();
+            }
         }
-
-        autocomplete
-            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
-            .await
-            .expect("Failure communicating with discord api");
+        if !content.is_empty() {
+            messages.push(content);
+        }
+        messages
     }
 
-    async fn autocomplete_join(&self, autocomplete: &CommandInteraction, ctx: &Context) {
-        let Some(guild_id) = autocomplete.guild_id else {
-            autocomplete
-                .create_response(
-                    &ctx.http,
-                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
-                )
-                .await
-                .expect("Failure communicating with discord api");
-            return;
+    /// Posts a single message of a ping, the first one of a command ping becomes its response.
+    async fn post_ping_message(
+        ctx: &Context,
+        request: &PingRequest,
+        origin: PingOrigin<'_>,
+        first: bool,
+        content: Option<String>,
+        embed: Option<CreateEmbed>,
+    ) -> Option<MessageId> {
+        // Mentions in the embed are never meant to ping anyone.
+        let allowed_mentions = if embed.is_some() {
+            CreateAllowedMentions::new()
+        } else {
+            CreateAllowedMentions::new().all_users(true)
         };
-        const SUGGESTIONS: usize = 5;
-        let member = autocomplete.member.as_ref().unwrap();
-        let member_admin = member
-            .permissions
-            .unwrap()
-            .contains(Permissions::MANAGE_MESSAGES);
-        let mut userid = member.user.id;
-
-        let mut filter: &str = "";
-        for field in &autocomplete.data.options {
-            if let CommandDataOptionValue::User(uid) = field.value {
-                userid = uid;
+        match origin {
+            PingOrigin::Command(command) if first => {
+                let mut message = CreateInteractionResponseMessage::new()
+                    .allowed_mentions(allowed_mentions)
+                    .content(content.unwrap_or_default());
+                if let Some(embed) = embed {
+                    message = message.embed(embed);
+                }
+                command
+                    .create_response(&ctx.http, CreateInteractionResponse::Message(message))
+                    .await
+                    .expect("Failed to send text response, see error for details.");
+                command
+                    .get_response(&ctx.http)
+                    .await
+                    .ok()
+                    .map(|response| response.id)
             }
-            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
-                if kind == CommandOptionType::String {
-                    filter = value;
+            PingOrigin::Command(command) => {
+                let mut message = CreateInteractionResponseFollowup::new()
+                    .allowed_mentions(allowed_mentions)
+                    .content(content.unwrap_or_default());
+                if let Some(embed) = embed {
+                    message = message.embed(embed);
+                }
+                command
+                    .create_followup(&ctx.http, message)
+                    .await
+                    .map(|followup| followup.id)
+                    .ok()
+            }
+            PingOrigin::Channel(reply_to) => {
+                let mut message = CreateMessage::new()
+                    .allowed_mentions(allowed_mentions)
+                    .content(content.unwrap_or_default());
+                if let Some(embed) = embed {
+                    message = message.embed(embed);
+                }
+                if let (true, Some(reply_to)) = (first, reply_to) {
+                    message = message.reference_message(
+                        serenity::model::prelude::MessageReference::from((
+                            request.channel_id,
+                            reply_to,
+                        )),
+                    );
+                }
+                // Not fatal, this also runs from the scheduler where nobody could retry.
+                match request.channel_id.send_message(&ctx.http, message).await {
+                    Ok(sent) => Some(sent.id),
+                    Err(why) => {
+                        println!("Failed to send ping: {:?}", why);
+                        None
+                    }
                 }
             }
         }
+    }
 
-        let mut aliases: Vec<String> = Vec::new();
-
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-
-        if let Ok(mut x) = db.clone().lock() {
-            aliases =
-                x.get_list_joinable_by_search(guild_id, userid, SUGGESTIONS, filter, member_admin);
+    /// Sends a prepared ping to its channel and the direct messages of its members, following
+    /// the delivery mode of its lists. Returns the ids of the messages sent in the channel.
+    async fn deliver_ping(
+        ctx: &Context,
+        request: &PingRequest,
+        prepared: &PreparedPing,
+        origin: PingOrigin<'_>,
+    ) -> Vec<MessageId> {
+        let mut messages: Vec<(Option<String>, Option<CreateEmbed>)> = vec![];
+        if let Some(embed) = Handler::compose_ping_embed(request) {
+            messages.push((None, Some(embed)));
         }
 
-        let mut resp = CreateAutocompleteResponse::new();
-        for list in aliases {
-            resp = resp.add_string_choice(&list, &list);
+        let lists = request.queries.join(", ");
+        let count = prepared.members.len();
+        let (header, mentioned, dm_targets): (String, &[UserId], Vec<UserId>) = match prepared
+            .delivery
+        {
+            _ if prepared.members.is_empty() => ("These lists are empty.".to_string(), &[], vec![]),
+            DeliveryMode::Channel => (
+                format!("Mentioning {} with {} members:\n", lists, count),
+                &prepared.members,
+                vec![],
+            ),
+            DeliveryMode::DirectMessage => (
+                format!(
+                    "Mentioning {} with {} members by direct message.",
+                    lists, count
+                ),
+                &[],
+                prepared.members.clone(),
+            ),
+            DeliveryMode::Both => (
+                format!("Mentioning {} with {} members:\n", lists, count),
+                &prepared.members,
+                prepared.members.clone(),
+            ),
+        };
+        for content in Handler::compose_ping(header, mentioned) {
+            messages.push((Some(content), None));
         }
 
-        autocomplete
-            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
-            .await
-            .expect("Failure communicating with discord api");
-    }
+        let mut message_ids = vec![];
+        for (i, (content, embed)) in messages.into_iter().enumerate() {
+            if let Some(message_id) =
+                Handler::post_ping_message(ctx, request, origin, i == 0, content, embed).await
+            {
+                message_ids.push(message_id);
+            }
+        }
+        if dm_targets.is_empty() {
+            return message_ids;
+        }
 
-    async fn autocomplete_leave(&self, autocomplete: &CommandInteraction, ctx: &Context) {
-        let Some(guild_id) = autocomplete.guild_id else {
-            autocomplete
-                .create_response(
+        let mut unreachable: Vec<UserId> = vec![];
+        let jump = message_ids.first().map_or(String::new(), |message_id| {
+            format!(
+                ": https://discord.com/channels/{}/{}/{}",
+                request.guild_id, request.channel_id, message_id
+            )
+        });
+        let mut dm = format!(
+            "<@{}> pinged {} in <#{}>{}",
+            request.user_id, lists, request.channel_id, jump
+        );
+        if let Some(message) = request.message.as_ref().filter(|m| !m.trim().is_empty()) {
+            dm += format!("\n>>> {}", Handler::sanitize_ping_message(message.trim())).as_str();
+        }
+        for member in dm_targets {
+            if prepared.dm_opt_outs.contains(&member) {
+                unreachable.push(member);
+                continue;
+            }
+            let sent = member
+                .direct_message(
                     &ctx.http,
-                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
+                    CreateMessage::new()
+                        .content(&dm)
+                        .allowed_mentions(CreateAllowedMentions::new()),
                 )
-                .await
-                .expect("Failure communicating with discord api");
-            return;
-        };
-        const SUGGESTIONS: usize = 5;
-        let member = autocomplete.member.as_ref().unwrap();
-        let member_admin = member
-            .permissions
-            .unwrap()
-            .contains(Permissions::MANAGE_MESSAGES);
-        let mut userid = member.user.id;
-
-        let mut filter = "";
-        for field in &autocomplete.data.options {
-            if let CommandDataOptionValue::User(uid) = field.value {
-                userid = uid;
+                .await;
+            // Members with closed direct messages are mentioned in the channel instead.
+            if sent.is_err() {
+                unreachable.push(member);
             }
-            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
-                if kind == CommandOptionType::String {
-                    filter = value;
+        }
+
+        if prepared.delivery == DeliveryMode::DirectMessage && !unreachable.is_empty() {
+            let header = "These members could not be reached by direct message:\n".to_string();
+            for content in Handler::compose_ping(header, &unreachable) {
+                if let Some(message_id) = Handler::post_ping_message(
+                    ctx,
+                    request,
+                    origin,
+                    message_ids.is_empty(),
+                    Some(content),
+                    None,
+                )
+                .await
+                {
+                    message_ids.push(message_id);
                 }
             }
         }
-        let mut aliases: Vec<String> = Vec::new();
-
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
+        message_ids
+    }
 
-        if let Ok(mut x) = db.clone().lock() {
-            aliases = x.get_list_membership_by_search(
-                guild_id,
-                userid,
-                SUGGESTIONS,
-                filter,
-                member_admin,
-            );
-        }
+    /// Defuses everyone, here and role mentions so a ping message can only mention the list.
+    fn sanitize_ping_message(message: &str) -> String {
+        message
+            .replace("@everyone", "@\u{200B}everyone")
+            .replace("@here", "@\u{200B}here")
+            .replace("<@&", "<@\u{200B}&")
+    }
 
-        let mut resp = CreateAutocompleteResponse::new();
-        for list in aliases {
-            resp = resp.add_string_choice(&list, &list);
+    /// The embed shown above the mentions when a message was attached to the ping.
+    fn compose_ping_embed(request: &PingRequest) -> Option<CreateEmbed> {
+        let message = request.message.as_ref()?.trim();
+        if message.is_empty() {
+            return None;
         }
+        Some(CreateEmbed::new().description(format!(
+            "{}\n\n— <@{}>",
+            Handler::sanitize_ping_message(message),
+            request.user_id
+        )))
+    }
+
+    /// When a ping was only held back by cooldowns, the time at which all of them have run out.
+    fn cooldown_end(invalid_lists: &[(String, ListInvalidReasons)]) -> Option<u64> {
+        invalid_lists
+            .iter()
+            .map(|(_, reason)| match reason {
+                ListInvalidReasons::OnGlobalCooldown(available)
+                | ListInvalidReasons::OnLocalCooldown(available) => Some(*available),
+                _ => None,
+            })
+            .try_fold(0, |end, available| {
+                available.map(|available| end.max(available))
+            })
+    }
+
+    /// Queued pings of the same lists to the same channel are considered duplicates, regardless
+    /// of spacing.
+    fn normalize_queries(queries: &[String]) -> Vec<String> {
+        let mut normalized = queries
+            .iter()
+            .map(|query| query.split_whitespace().collect::<Vec<&str>>().join(" "))
+            .collect::<Vec<String>>();
+        normalized.sort();
+        normalized
+    }
+
+    fn compose_ping_rejection(invalid_lists: Vec<(String, ListInvalidReasons)>) -> String {
+        let mut content = String::new();
+        for falselist in invalid_lists {
+            content += match falselist.1 {
+                ListInvalidReasons::ChannelRestrictPing => {
+                    "\nPings are not allowed in this channel.".to_string()
+                }
+                ListInvalidReasons::DoesNotExist => {
+                    format!("\nThe list {} does not exist.", falselist.0)
+                }
+                ListInvalidReasons::GuildRestrictPing => {
+                    "\nYou do not have permission to ping in this server.".to_string()
+                }
+                ListInvalidReasons::ListRestrictPing => {
+                    format!("\nThe list {} cannot be pinged.", falselist.0)
+                }
+                ListInvalidReasons::OnGlobalCooldown(available) => {
+                    format!("\nAnother ping has happed recently, please try again <t:{}:R> (<t:{}:T>).", available, available)
+                }
+                ListInvalidReasons::OnLocalCooldown(available) => {
+                    format!("\nThe list {} has been pinged recently, please try again <t:{}:R> (<t:{}:T>) or exclude this list.", falselist.0, available, available)
+                }
+                ListInvalidReasons::RoleRestrictPing => {
+                    "One of your roles prevents you from using the ping command.\n".to_string()
+                }
+                ListInvalidReasons::MalformedQuery(reason) => {
+                    format!("\nCould not understand \"{}\": {}.", falselist.0, reason)
+                }
+                ListInvalidReasons::MembersUnavailable => {
+                    "\nA problem occured retrieving guild members, try again later.".to_string()
+                }
+                ListInvalidReasons::QuotaExceeded(reset) => {
+                    format!(
+                        "\nYou have used up your pings for now, you can ping again <t:{}:R>.",
+                        reset
+                    )
+                }
+                ListInvalidReasons::MessageTooLong => {
+                    format!(
+                        "\nThe message can be at most {} characters long.",
+                        PING_MESSAGE_MAX_LENGTH
+                    )
+                }
+            }
+            .as_str()
+        }
+        content
+    }
+
+    async fn handle_ping(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let member = command.member.as_ref().unwrap();
+        let mut request = PingRequest {
+            guild_id,
+            channel_id: command.channel_id,
+            user_id: member.user.id,
+            roles: member.roles.clone(),
+            as_admin: Handler::can_manage_messages(command),
+            queries: vec![],
+            message: None,
+        };
+        let mut long_message = false;
+        for option in &command.data.options {
+            match (option.name.as_str(), &option.value) {
+                ("message", CommandDataOptionValue::String(message)) => {
+                    request.message = Some(message.clone())
+                }
+                ("long_message", CommandDataOptionValue::Boolean(value)) => long_message = *value,
+                (_, CommandDataOptionValue::String(query)) => request.queries.push(query.clone()),
+                _ => panic!("Invalid /ping definition, unexpected option type"),
+            }
+        }
+
+        if long_message && request.message.is_none() {
+            let message_input = CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Paragraph, "Message", "message")
+                    .placeholder("What is this ping about?")
+                    .max_length(PING_MESSAGE_MAX_LENGTH as u16),
+            );
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Modal(
+                        CreateModal::new(
+                            format!("ping_message:{}", command.id),
+                            "Add a message to your ping",
+                        )
+                        .components(vec![message_input]),
+                    ),
+                )
+                .await
+                .expect("Failed to open message form");
+            let mut data = ctx.data.write().await;
+            let BotData { pending_pings, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            pending_pings.insert(
+                command.id.get(),
+                (
+                    request,
+                    serenity::model::Timestamp::now().unix_timestamp() as u64,
+                ),
+            );
+            return;
+        }
+
+        let prepared = match Handler::prepare_ping(ctx, &request).await {
+            Ok(prepared) => prepared,
+            Err(invalid_lists) => {
+                Handler::reject_ping(command, ctx, request, invalid_lists).await;
+                return;
+            }
+        };
+        if let Some(preview) = Handler::compose_ping_preview(ctx, &request, &prepared).await {
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .embed(preview)
+                            .components(vec![Handler::confirm_ping_buttons(command.id.get(), None)])
+                            .ephemeral(true),
+                    ),
+                )
+                .await
+                .expect("Failed to send text response, see error for details.");
+            let mut data = ctx.data.write().await;
+            let BotData { pending_pings, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            pending_pings.insert(
+                command.id.get(),
+                (
+                    request,
+                    serenity::model::Timestamp::now().unix_timestamp() as u64,
+                ),
+            );
+            return;
+        }
+        if let Err(invalid_lists) = Handler::commit_ping(ctx, &request, &prepared).await {
+            Handler::reject_ping(command, ctx, request, invalid_lists).await;
+            return;
+        }
+        let message_ids =
+            Handler::deliver_ping(ctx, &request, &prepared, PingOrigin::Command(command)).await;
+        Handler::record_ping(ctx, &request, &prepared, message_ids).await;
+    }
+
+    /// Tells the user why their /ping was refused, offering to queue it when only cooldowns
+    /// are in the way.
+    async fn reject_ping(
+        command: &CommandInteraction,
+        ctx: &Context,
+        request: PingRequest,
+        invalid_lists: Vec<(String, ListInvalidReasons)>,
+    ) {
+        let cooldown_end = Handler::cooldown_end(&invalid_lists);
+        let content = Handler::compose_ping_rejection(invalid_lists);
+        let Some(cooldown_end) = cooldown_end else {
+            Handler::send_text(&content, command, ctx, true).await;
+            return;
+        };
+        let queue_button = CreateButton::new(format!("ping_queue:{}:{}", command.id, cooldown_end))
+            .label("Send when available")
+            .style(ButtonStyle::Primary);
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![CreateActionRow::Buttons(vec![queue_button])])
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .expect("Failed to send text response, see error for details.");
+        let mut data = ctx.data.write().await;
+        let BotData { pending_pings, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        pending_pings.insert(
+            command.id.get(),
+            (
+                request,
+                serenity::model::Timestamp::now().unix_timestamp() as u64,
+            ),
+        );
+    }
+
+    /// A summary of a ping that reaches more members than the guild wants to allow without
+    /// confirmation, or `None` if it can be sent right away.
+    async fn compose_ping_preview(
+        ctx: &Context,
+        request: &PingRequest,
+        prepared: &PreparedPing,
+    ) -> Option<CreateEmbed> {
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        let Ok(mut x) = db.lock() else {
+            return None;
+        };
+        let threshold = x.get_confirm_threshold(request.guild_id);
+        if threshold == 0 || prepared.members.len() as u64 <= threshold {
+            return None;
+        }
+
+        let (general_cooldown, _, guild_cooldown) = x.get_guild_ping_data(request.guild_id);
+        let mut cooldowns = vec![];
+        let last_global = x.get_guild_last_ping(request.guild_id);
+        if last_global + general_cooldown >= prepared.timestamp {
+            cooldowns.push(format!(
+                "The server is on cooldown until <t:{}:R>",
+                last_global + general_cooldown + 1
+            ));
+        }
+        let mut lists = String::new();
+        for list_id in &prepared.list_ids {
+            let name = x
+                .get_list_names(*list_id)
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            let (mut cooldown, _, _) = x.get_list_permissions(*list_id);
+            if cooldown == -1 {
+                cooldown = guild_cooldown as i64;
+            }
+            let last_ping = x.get_list_last_ping(*list_id);
+            if last_ping + cooldown as u64 >= prepared.timestamp {
+                cooldowns.push(format!(
+                    "{} is on cooldown until <t:{}:R>",
+                    name,
+                    last_ping + cooldown as u64 + 1
+                ));
+            }
+            lists += format!(
+                "- {}: {} members, on cooldown for {} seconds after this ping\n",
+                name,
+                x.get_effective_members_in_list(*list_id).len(),
+                cooldown
+            )
+            .as_str();
+        }
+        // Active cooldowns only get this far for members allowed to skip them
+        let cooldowns = match cooldowns.is_empty() {
+            true => "None of the lists are on cooldown".to_string(),
+            false => format!(
+                "{}\nConfirming skips these cooldowns.",
+                cooldowns.join("\n")
+            ),
+        };
+        Some(
+            CreateEmbed::new()
+                .color((255, 127, 28))
+                .title(format!(
+                    "This will mention {} members, are you sure?",
+                    prepared.members.len()
+                ))
+                .description(format!("Pinging {}", request.queries.join(", ")))
+                .field("Lists", lists, false)
+                .field("Cooldowns", cooldowns, false)
+                .field("Delivery", format!("{}", prepared.delivery), true),
+        )
+    }
+
+    /// The Confirm and Cancel buttons of a ping preview, the ping is kept in pending_pings
+    /// under `key` and optionally replies to a message.
+    fn confirm_ping_buttons(key: u64, message_id: Option<MessageId>) -> CreateActionRow {
+        let suffix = match message_id {
+            Some(message_id) => format!("{}:{}", key, message_id),
+            None => key.to_string(),
+        };
+        CreateActionRow::Buttons(vec![
+            CreateButton::new(format!("ping_confirm:{}", suffix))
+                .label("Confirm")
+                .style(ButtonStyle::Danger),
+            CreateButton::new(format!("ping_cancel:{}", suffix))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ])
+    }
+
+    async fn confirm_ping_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let mut fields = component.data.custom_id.split(':');
+        let action = fields.next().unwrap_or_default();
+        let Some(Ok(interaction_id)) = fields.next().map(str::parse::<u64>) else {
+            return;
+        };
+        let message_id = fields
+            .next()
+            .and_then(|id| id.parse::<u64>().ok())
+            .map(MessageId::new);
+        let request = {
+            let mut data = ctx.data.write().await;
+            let BotData { pending_pings, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            pending_pings
+                .remove(&interaction_id)
+                .map(|(request, _)| request)
+        };
+
+        let content = match (action, request) {
+            (_, None) => "This ping has expired, please use /ping again.".to_string(),
+            ("ping_cancel", Some(_)) => "Ping cancelled.".to_string(),
+            // Everything is checked again, cooldowns may have started since the preview.
+            (_, Some(request)) => Handler::describe_channel_ping(
+                Handler::send_ping_to_channel(ctx, &request, message_id).await,
+            ),
+        };
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .embeds(vec![])
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    async fn queue_ping_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let mut fields = component
+            .data
+            .custom_id
+            .split(':')
+            .skip(1)
+            .map(|field| field.parse::<u64>().unwrap_or(0));
+        let (Some(interaction_id), Some(cooldown_end)) = (fields.next(), fields.next()) else {
+            return;
+        };
+
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            pending_pings,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        let mut content = "This ping has expired, please use /ping again.".to_string();
+        if let Some((request, _)) = pending_pings.remove(&interaction_id) {
+            if let Ok(mut x) = db.clone().lock() {
+                let normalized = Handler::normalize_queries(&request.queries);
+                let queued = x
+                    .get_scheduled_pings(request.guild_id)
+                    .expect("Failed to read scheduled pings")
+                    .into_iter()
+                    .find(|ping| {
+                        ping.queued
+                            && ping.channel_id == request.channel_id
+                            && Handler::normalize_queries(&ping.queries) == normalized
+                    });
+                content = match queued {
+                    Some(ping) => format!(
+                        "This ping is already queued as #{}, it will be sent <t:{}:R>.",
+                        ping.id, ping.next_time
+                    ),
+                    None => {
+                        let id = x
+                            .add_scheduled_ping(&ScheduledPing {
+                                id: 0,
+                                guild_id: request.guild_id,
+                                channel_id: request.channel_id,
+                                user_id: request.user_id,
+                                queries: request.queries.clone(),
+                                note: request.message.clone().unwrap_or_default(),
+                                next_time: cooldown_end,
+                                interval: 0,
+                                schedule: None,
+                                queued: true,
+                            })
+                            .expect("Failed to store scheduled ping");
+                        format!(
+                            "Queued as #{}, it will be sent once the cooldowns run out <t:{}:R>.",
+                            id, cooldown_end
+                        )
+                    }
+                };
+            }
+        }
+
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    async fn ping_message_from_modal(&self, modal: &ModalInteraction, ctx: &Context) {
+        let Some(interaction_id) = modal
+            .data
+            .custom_id
+            .strip_prefix("ping_message:")
+            .and_then(|id| id.parse::<u64>().ok())
+        else {
+            return;
+        };
+        let request = {
+            let mut data = ctx.data.write().await;
+            let BotData { pending_pings, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            pending_pings
+                .remove(&interaction_id)
+                .map(|(request, _)| request)
+        };
+        let response = match request {
+            None => CreateInteractionResponseMessage::new()
+                .content("This ping has expired, please use /ping again."),
+            Some(mut request) => {
+                request.message = Handler::modal_input(modal, "message");
+                Handler::send_or_preview_ping(ctx, modal.id.get(), request, None).await
+            }
+        };
+        modal
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(response.ephemeral(true)),
+            )
+            .await
+            .ok();
+    }
+
+    /// The value of a text input in a submitted modal.
+    fn modal_input(modal: &ModalInteraction, custom_id: &str) -> Option<String> {
+        modal
+            .data
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == custom_id => {
+                    input.value.clone()
+                }
+                _ => None,
+            })
+    }
+
+    async fn handle_context_ping(&self, command: &CommandInteraction, ctx: &Context) {
+        if command.guild_id.is_none() {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        }
+        let Some(message_id) = command.data.target_id.map(|id| id.to_message_id()) else {
+            Handler::send_text("No message to reply to was found.", command, ctx, true).await;
+            return;
+        };
+
+        let list_input = CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "List", "list")
+                .placeholder("A list name, part of one, or an expression like a & b")
+                .max_length(100),
+        );
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Modal(
+                    CreateModal::new(
+                        format!("ping_context:{}", message_id),
+                        "Ping a list in reply to this message",
+                    )
+                    .components(vec![list_input]),
+                ),
+            )
+            .await
+            .expect("Failed to open list picker");
+    }
+
+    /// Sends a ping that was not requested through a slash command straight to its channel,
+    /// optionally as a reply.
+    async fn send_ping_to_channel(
+        ctx: &Context,
+        request: &PingRequest,
+        message_id: Option<MessageId>,
+    ) -> Result<(), Vec<(String, ListInvalidReasons)>> {
+        let prepared = Handler::prepare_ping(ctx, request).await?;
+        Handler::send_prepared_ping_to_channel(ctx, request, &prepared, message_id).await
+    }
+
+    async fn send_prepared_ping_to_channel(
+        ctx: &Context,
+        request: &PingRequest,
+        prepared: &PreparedPing,
+        message_id: Option<MessageId>,
+    ) -> Result<(), Vec<(String, ListInvalidReasons)>> {
+        Handler::commit_ping(ctx, request, prepared).await?;
+        let message_ids =
+            Handler::deliver_ping(ctx, request, prepared, PingOrigin::Channel(message_id)).await;
+        Handler::record_ping(ctx, request, prepared, message_ids).await;
+        Ok(())
+    }
+
+    /// Like `send_ping_to_channel`, but large pings get a preview to confirm first, which is
+    /// kept under `key`. Returns the response to show the user.
+    async fn send_or_preview_ping(
+        ctx: &Context,
+        key: u64,
+        request: PingRequest,
+        message_id: Option<MessageId>,
+    ) -> CreateInteractionResponseMessage {
+        let prepared = match Handler::prepare_ping(ctx, &request).await {
+            Ok(prepared) => prepared,
+            Err(invalid_lists) => {
+                return CreateInteractionResponseMessage::new()
+                    .content(Handler::compose_ping_rejection(invalid_lists))
+                    .components(vec![]);
+            }
+        };
+        if let Some(preview) = Handler::compose_ping_preview(ctx, &request, &prepared).await {
+            let mut data = ctx.data.write().await;
+            let BotData { pending_pings, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            pending_pings.insert(
+                key,
+                (
+                    request,
+                    serenity::model::Timestamp::now().unix_timestamp() as u64,
+                ),
+            );
+            return CreateInteractionResponseMessage::new()
+                .embed(preview)
+                .components(vec![Handler::confirm_ping_buttons(key, message_id)]);
+        }
+        let content = Handler::describe_channel_ping(
+            Handler::send_prepared_ping_to_channel(ctx, &request, &prepared, message_id).await,
+        );
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .components(vec![])
+    }
+
+    /// The text shown to a user after their ping was sent to a channel for them.
+    fn describe_channel_ping(result: Result<(), Vec<(String, ListInvalidReasons)>>) -> String {
+        match result {
+            Ok(()) => "Ping sent.".to_string(),
+            Err(invalid_lists) => Handler::compose_ping_rejection(invalid_lists),
+        }
+    }
+
+    async fn context_ping_from_modal(&self, modal: &ModalInteraction, ctx: &Context) {
+        let Some(guild_id) = modal.guild_id else {
+            return;
+        };
+        let Some(message_id) = modal
+            .data
+            .custom_id
+            .strip_prefix("ping_context:")
+            .and_then(|id| id.parse::<u64>().ok())
+            .map(MessageId::new)
+        else {
+            return;
+        };
+        let member = modal
+            .member
+            .as_ref()
+            .expect("Modal not submitted by a member");
+        let as_admin = member
+            .permissions
+            .expect("Member reference not from interaction")
+            .contains(Permissions::MANAGE_MESSAGES);
+
+        let text = Handler::modal_input(modal, "list")
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        // Exact names and expressions are pinged straight away, anything else is a search.
+        let mut suggestions: Vec<String> = vec![];
+        let mut is_query = false;
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.clone().lock() {
+                is_query = x.get_list_id_by_name(&text, guild_id).is_some()
+                    || PingQuery::parse(&text).is_ok_and(|query| query.list_names().len() > 1);
+                if !is_query {
+                    suggestions = x.get_list_aliases_by_search(guild_id, 0, 25, &text, as_admin);
+                }
+            }
+        }
+
+        let response = if is_query {
+            let request = PingRequest {
+                guild_id,
+                channel_id: modal.channel_id,
+                user_id: member.user.id,
+                roles: member.roles.clone(),
+                as_admin,
+                queries: vec![text],
+                message: None,
+            };
+            Handler::send_or_preview_ping(ctx, modal.id.get(), request, Some(message_id))
+                .await
+                .ephemeral(true)
+        } else if suggestions.is_empty() {
+            CreateInteractionResponseMessage::new()
+                .content(format!("No lists matching \"{}\" were found.", text))
+                .ephemeral(true)
+        } else {
+            let options = suggestions
+                .iter()
+                .map(|alias| CreateSelectMenuOption::new(alias, alias))
+                .collect::<Vec<CreateSelectMenuOption>>();
+            let max_values = min(5, options.len() as u8);
+            let select_menu = CreateSelectMenu::new(
+                format!("ping_context:{}", message_id),
+                serenity::all::CreateSelectMenuKind::String { options },
+            )
+            .placeholder("Pick the lists to ping")
+            .max_values(max_values);
+            CreateInteractionResponseMessage::new()
+                .components(vec![CreateActionRow::SelectMenu(select_menu)])
+                .ephemeral(true)
+        };
+
+        modal
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await
+            .ok();
+    }
+
+    async fn context_ping_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let (Some(guild_id), Some(member)) = (component.guild_id, component.member.as_ref()) else {
+            return;
+        };
+        let Some(message_id) = component
+            .data
+            .custom_id
+            .strip_prefix("ping_context:")
+            .and_then(|id| id.parse::<u64>().ok())
+            .map(MessageId::new)
+        else {
+            return;
+        };
+        let ComponentInteractionDataKind::StringSelect { ref values } = component.data.kind else {
+            return;
+        };
+
+        let request = PingRequest {
+            guild_id,
+            channel_id: component.channel_id,
+            user_id: member.user.id,
+            roles: member.roles.clone(),
+            as_admin: member
+                .permissions
+                .expect("Member reference not from interaction")
+                .contains(Permissions::MANAGE_MESSAGES),
+            queries: values.clone(),
+            message: None,
+        };
+        let response =
+            Handler::send_or_preview_ping(ctx, component.id.get(), request, Some(message_id)).await;
+
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(response),
+            )
+            .await
+            .ok();
+    }
+
+    /// The best matching list names for autocomplete with how many members each list reaches.
+    fn ranked_suggestions(
+        x: &mut Database,
+        indexes: &mut HashMap<GuildId, AliasIndex>,
+        guild_id: GuildId,
+        filter: &str,
+        accept: impl Fn(&IndexedAlias) -> bool,
+    ) -> Vec<(String, usize)> {
+        const SUGGESTIONS: usize = 5;
+        let revision = x.get_alias_revision();
+        if indexes
+            .get(&guild_id)
+            .is_none_or(|index| index.revision != revision)
+        {
+            let index = AliasIndex::new(revision, x.get_alias_index_entries(guild_id));
+            indexes.insert(guild_id, index);
+        }
+        indexes[&guild_id]
+            .search(filter, SUGGESTIONS, accept)
+            .into_iter()
+            .map(|alias| {
+                (
+                    alias.name.clone(),
+                    x.get_effective_members_in_list(alias.list_id).len(),
+                )
+            })
+            .collect()
+    }
+
+    fn list_choices(suggestions: Vec<(String, usize)>) -> CreateAutocompleteResponse {
+        let mut resp = CreateAutocompleteResponse::new();
+        for (value, members) in suggestions {
+            if value.chars().count() > 100 {
+                continue;
+            }
+            let label = format!("{} ({} members)", value, members);
+            if label.chars().count() > 100 {
+                resp = resp.add_string_choice(&value, &value);
+            } else {
+                resp = resp.add_string_choice(label, value);
+            }
+        }
+        resp
+    }
+
+    async fn autocomplete_ping(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = autocomplete.guild_id else {
+            autocomplete
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
+                )
+                .await
+                .expect("Failure communicating with discord api");
+            return;
+        };
+        let member = autocomplete.member.as_ref().unwrap();
+        let member_admin = member
+            .permissions
+            .expect("member reference did not originate from interaction")
+            .contains(Permissions::MANAGE_MESSAGES);
+
+        let mut filter = "";
+        for field in &autocomplete.data.options {
+            if let CommandDataOptionValue::Autocomplete { kind: _, ref value } = field.value {
+                filter = value;
+            }
+        }
+        let mut suggestions = Vec::new();
+
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            alias_indexes,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        // Expressions are completed one list name at a time
+        let expression = matches!(autocomplete.data.name.as_str(), "ping" | "schedule_ping");
+        let (prefix, filter) = if expression {
+            ping_query::split_last_term(filter)
+        } else {
+            ("", filter)
+        };
+
+        if let Ok(mut x) = db.lock() {
+            // /info also describes lists that cannot be pinged, but not hidden ones
+            let info = autocomplete.data.name == "info";
+            suggestions =
+                Handler::ranked_suggestions(&mut x, alias_indexes, guild_id, filter, |alias| {
+                    member_admin
+                        || (info && alias.visible)
+                        || (!info && alias.ping_permission != PERMISSION::DENY)
+                });
+        }
+
+        if expression {
+            suggestions = suggestions
+                .into_iter()
+                .map(|(name, members)| (ping_query::complete_term(prefix, &name), members))
+                .collect();
+        }
+        let resp = Handler::list_choices(suggestions);
+
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+            .await
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn autocomplete_join(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = autocomplete.guild_id else {
+            autocomplete
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
+                )
+                .await
+                .expect("Failure communicating with discord api");
+            return;
+        };
+        let member = autocomplete.member.as_ref().unwrap();
+        let member_admin = member
+            .permissions
+            .unwrap()
+            .contains(Permissions::MANAGE_MESSAGES);
+        let mut userid = member.user.id;
+
+        let mut filter: &str = "";
+        for field in &autocomplete.data.options {
+            if let CommandDataOptionValue::User(uid) = field.value {
+                userid = uid;
+            }
+            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
+                if kind == CommandOptionType::String {
+                    filter = value;
+                }
+            }
+        }
+
+        let mut suggestions = Vec::new();
+
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            alias_indexes,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        if let Ok(mut x) = db.lock() {
+            let joined = x
+                .get_lists_with_member(guild_id, userid)
+                .unwrap_or_default();
+            suggestions =
+                Handler::ranked_suggestions(&mut x, alias_indexes, guild_id, filter, |alias| {
+                    (member_admin || alias.join_permission != PERMISSION::DENY)
+                        && !joined.contains(&alias.list_id)
+                });
+        }
+
+        let resp = Handler::list_choices(suggestions);
+
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+            .await
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn autocomplete_leave(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = autocomplete.guild_id else {
+            autocomplete
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
+                )
+                .await
+                .expect("Failure communicating with discord api");
+            return;
+        };
+        let member = autocomplete.member.as_ref().unwrap();
+        let member_admin = member
+            .permissions
+            .unwrap()
+            .contains(Permissions::MANAGE_MESSAGES);
+        let mut userid = member.user.id;
+
+        let mut filter = "";
+        for field in &autocomplete.data.options {
+            if let CommandDataOptionValue::User(uid) = field.value {
+                userid = uid;
+            }
+            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
+                if kind == CommandOptionType::String {
+                    filter = value;
+                }
+            }
+        }
+        let mut suggestions = Vec::new();
+
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            alias_indexes,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        if let Ok(mut x) = db.lock() {
+            let joined = x
+                .get_lists_with_member(guild_id, userid)
+                .unwrap_or_default();
+            suggestions =
+                Handler::ranked_suggestions(&mut x, alias_indexes, guild_id, filter, |alias| {
+                    (member_admin || alias.ping_permission != PERMISSION::DENY)
+                        && joined.contains(&alias.list_id)
+                });
+        }
+
+        let resp = Handler::list_choices(suggestions);
+
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+            .await
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn add_member(
+        &self,
+        guild_id: GuildId,
+        list_name: &str,
+        member_id: UserId,
+        authority: JoinAuthority,
+        duration: Option<u64>,
+        ctx: &Context,
+    ) -> JoinResult {
+        let mut approval = None;
+        let (list_id, role) = {
+            let mut data = ctx.data.write().await;
+            let BotData { database: db, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+
+            let Ok(mut x) = db.lock() else {
+                return JoinResult::BotError;
+            };
+            let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) else {
+                return JoinResult::ListDoesNotExist;
+            };
+            let (_, list_join_permission, _) = x.get_list_permissions(list_id);
+            if list_join_permission == PERMISSION::DENY && authority != JoinAuthority::Moderator {
+                return JoinResult::MissingPerms;
+            }
+            let role = x.get_list_role(list_id);
+            // Managers could otherwise hand out any role linked to their list
+            if role.is_some() && authority == JoinAuthority::Manager {
+                return JoinResult::MissingPerms;
+            }
+            if authority == JoinAuthority::Member
+                && x.get_requires_approval(list_id)
+                && !x.is_list_manager(list_id, member_id)
+            {
+                if x.has_member(member_id, list_id) {
+                    return JoinResult::AlreadyMember;
+                }
+                let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+                if let Some(available) = x.get_join_request_cooldown(list_id, member_id, now) {
+                    return JoinResult::ApprovalDenied(available);
+                }
+                let Some(request_id) = x
+                    .add_join_request(list_id, member_id, now, duration)
+                    .expect("Failed to store join request")
+                else {
+                    return JoinResult::ApprovalPending;
+                };
+                approval = Some((
+                    request_id,
+                    x.get_approval_channel(guild_id),
+                    x.get_list_managers(list_id),
+                ));
+                (list_id, None)
+            } else if let Some(role_id) = role {
+                // The membership is stored once the role is granted, see below
+                if x.has_member(member_id, list_id) {
+                    return JoinResult::AlreadyMember;
+                }
+                (list_id, Some(role_id))
+            } else {
+                match x.add_member(member_id, list_id) {
+                    JoinResult::Succes => {
+                        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+                        x.set_membership_expiry(member_id, list_id, now, duration)
+                            .expect("Failed to set membership expiry");
+                        (list_id, None)
+                    }
+                    other => return other,
+                }
+            }
+        };
+
+        if let Some((request_id, channel, managers)) = approval {
+            return Handler::post_join_request(
+                ctx, guild_id, request_id, list_name, member_id, channel, managers,
+            )
+            .await;
+        }
+
+        // Role-backed lists only get the membership once the role is granted, otherwise a member
+        // update arriving in between would remove it again
+        if let Some(role_id) = role {
+            if ctx
+                .http
+                .add_member_role(
+                    guild_id,
+                    member_id,
+                    role_id,
+                    Some("Joined a role-backed list"),
+                )
+                .await
+                .is_err()
+            {
+                return JoinResult::RoleUnavailable;
+            }
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let Ok(mut x) = db.lock() else {
+                return JoinResult::BotError;
+            };
+            // The member update for the new role may have stored the membership already
+            if let JoinResult::Succes | JoinResult::AlreadyMember = x.add_member(member_id, list_id)
+            {
+                let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+                x.set_membership_expiry(member_id, list_id, now, duration)
+                    .expect("Failed to set membership expiry");
+            }
+        }
+        JoinResult::Succes
+    }
+
+    /// Asks the approval channel, or otherwise the list managers, to decide on a join request.
+    async fn post_join_request(
+        ctx: &Context,
+        guild_id: GuildId,
+        request_id: u64,
+        list_name: &str,
+        member_id: UserId,
+        channel: Option<ChannelId>,
+        managers: Vec<UserId>,
+    ) -> JoinResult {
+        let guild_name = guild_id.name(&ctx.cache).unwrap_or_default();
+        let message = CreateMessage::new()
+            .content(format!(
+                "<@{}> asks to join the list {} in {}.",
+                member_id, list_name, guild_name
+            ))
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("join_approve:{}", request_id))
+                    .label("Approve")
+                    .style(ButtonStyle::Success),
+                CreateButton::new(format!("join_deny:{}", request_id))
+                    .label("Deny")
+                    .style(ButtonStyle::Danger),
+            ])]);
+
+        let mut delivered = false;
+        if let Some(channel_id) = channel {
+            delivered = channel_id.send_message(&ctx.http, message).await.is_ok();
+        } else {
+            for manager in managers {
+                delivered |= manager
+                    .direct_message(&ctx.http, message.clone())
+                    .await
+                    .is_ok();
+            }
+        }
+        if delivered {
+            return JoinResult::ApprovalRequested;
+        }
+
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        if let Ok(mut x) = db.lock() {
+            x.remove_join_request(request_id)
+                .expect("Failed to remove join request");
+        };
+        JoinResult::ApprovalUnavailable
+    }
+
+    /// Settles a join request from its Approve or Deny button and lets the requester know.
+    async fn join_request_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let (action, request_id) = component.data.custom_id.split_once(':').unwrap_or_default();
+        let Ok(request_id) = request_id.parse::<u64>() else {
+            return;
+        };
+
+        let mut outcome: Result<(GuildId, String, UserId, Option<u64>), &str> =
+            Err("This request was already handled or has expired.");
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                if let Some((list_id, member_id, duration)) = x
+                    .get_join_request(request_id)
+                    .expect("Failed to read join requests")
+                {
+                    let moderator = component.member.as_ref().is_some_and(|member| {
+                        member
+                            .permissions
+                            .is_some_and(|p| p.contains(Permissions::MANAGE_MESSAGES))
+                    });
+                    if !moderator && !x.is_list_manager(list_id, component.user.id) {
+                        outcome = Err("Only moderators and managers of this list can decide.");
+                    } else {
+                        if action == "join_approve" {
+                            x.remove_join_request(request_id)
+                                .expect("Failed to remove join request");
+                        } else {
+                            let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+                            x.deny_join_request(request_id, now)
+                                .expect("Failed to deny join request");
+                        }
+                        let guild_id = x.get_list_guild(list_id).expect("List without guild");
+                        let list_name = x.get_list_names(list_id).swap_remove(0);
+                        outcome = Ok((guild_id, list_name, member_id, duration));
+                    }
+                }
+            };
+        }
+
+        let (guild_id, list_name, member_id, duration) = match outcome {
+            Ok(request) => request,
+            Err(reason) => {
+                component
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(reason)
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await
+                    .ok();
+                return;
+            }
+        };
+
+        // The request passed the join checks when it was made
+        let (decision, notice) = if action == "join_approve" {
+            match self
+                .add_member(
+                    guild_id,
+                    &list_name,
+                    member_id,
+                    JoinAuthority::Moderator,
+                    duration,
+                    ctx,
+                )
+                .await
+            {
+                JoinResult::Succes | JoinResult::AlreadyMember => (
+                    "Approved",
+                    format!("Your request to join the list {} was approved.", list_name),
+                ),
+                JoinResult::RoleUnavailable => (
+                    "Approved, but the role of the list could not be given",
+                    format!(
+                        "Your request to join the list {} was approved, but the bot could not give you its role.",
+                        list_name
+                    ),
+                ),
+                _ => (
+                    "Could not be approved",
+                    format!(
+                        "Your request to join the list {} could not be completed.",
+                        list_name
+                    ),
+                ),
+            }
+        } else {
+            (
+                "Denied",
+                format!("Your request to join the list {} was denied.", list_name),
+            )
+        };
+
+        member_id
+            .direct_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .content(notice)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await
+            .ok();
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!(
+                            "{}\n{} by <@{}>.",
+                            component.message.content, decision, component.user.id
+                        ))
+                        .allowed_mentions(CreateAllowedMentions::new())
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    async fn remove_member(
+        &self,
+        guild_id: GuildId,
+        list_name: &str,
+        member_id: UserId,
+        as_admin: bool,
+        ctx: &Context,
+    ) -> JoinResult {
+        let (list_id, role) = {
+            let mut data = ctx.data.write().await;
+            let BotData { database: db, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+
+            let Ok(mut x) = db.lock() else {
+                return JoinResult::BotError;
+            };
+            let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) else {
+                return JoinResult::ListDoesNotExist;
+            };
+            let (_, list_join_permission, _) = x.get_list_permissions(list_id);
+            if list_join_permission == PERMISSION::DENY && !as_admin {
+                return JoinResult::MissingPerms;
+            }
+            if !x
+                .remove_member(member_id, list_id)
+                .expect("Failed to remove membership.")
+            {
+                return JoinResult::AlreadyMember;
+            }
+            (list_id, x.get_list_role(list_id))
+        };
+
+        // Put the membership back if the role could not be taken away, otherwise
+        // the next role sync would silently re-add the member anyway
+        if let Some(role_id) = role {
+            if ctx
+                .http
+                .remove_member_role(
+                    guild_id,
+                    member_id,
+                    role_id,
+                    Some("Left a role-backed list"),
+                )
+                .await
+                .is_err()
+            {
+                let data = ctx.data.read().await;
+                let BotData { database: db, .. } = data
+                    .get::<DB>()
+                    .expect("Could not find database in bot data");
+                if let Ok(mut x) = db.lock() {
+                    x.add_member(member_id, list_id);
+                }
+                return JoinResult::RoleUnavailable;
+            }
+        }
+        JoinResult::Succes
+    }
+
+    /// The optional `duration` of /join and /add in seconds, memberships are permanent without it.
+    fn membership_duration(command: &CommandInteraction) -> Option<u64> {
+        command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "duration")
+            .and_then(|option| option.value.as_i64())
+            .map(|duration| duration as u64)
+    }
+
+    fn describe_expiry(duration: Option<u64>) -> String {
+        duration.map_or(String::new(), |duration| {
+            let expires_at = serenity::model::Timestamp::now().unix_timestamp() as u64 + duration;
+            format!(" until <t:{}:f>", expires_at)
+        })
+    }
+
+    fn describe_join(result: JoinResult, list_name: &str, duration: Option<u64>) -> String {
+        match result {
+            JoinResult::AlreadyMember => format!("You already joined the list {}", list_name),
+            JoinResult::Succes => format!(
+                "Added to list {}{}",
+                list_name,
+                Handler::describe_expiry(duration)
+            ),
+            JoinResult::ListDoesNotExist => format!("The list {} does not exist", list_name),
+            JoinResult::MissingPerms => format!(
+                "You do not have permission to join the list {}.",
+                list_name
+            ),
+            JoinResult::BotError => format!(
+                "Something went wrong trying to join the \"{}\" list.",
+                list_name
+            ),
+            JoinResult::ApprovalRequested => format!(
+                "Asked to join the list {}, you will get a message once your request is handled",
+                list_name
+            ),
+            JoinResult::ApprovalPending => format!(
+                "You already asked to join the list {}, please wait for a decision",
+                list_name
+            ),
+            JoinResult::ApprovalUnavailable => format!(
+                "The list {} needs approval, but nobody could be asked to approve your request",
+                list_name
+            ),
+            JoinResult::ApprovalDenied(available) => format!(
+                "Your request to join the list {} was denied, you can ask again <t:{}:R>",
+                list_name, available
+            ),
+            JoinResult::RoleUnavailable => format!("Could not give you the role of list {}, ask a moderator to check the bot's role permissions", list_name),
+        }
+    }
+
+    fn describe_leave(result: JoinResult, list_name: &str) -> String {
+        match result {
+            JoinResult::Succes => format!("Removed from list {}", list_name),
+            JoinResult::AlreadyMember => format!("You were not in the list {}", list_name),
+            JoinResult::ListDoesNotExist => format!("The list {} does not exist", list_name),
+            JoinResult::MissingPerms => format!(
+                "You do not have permission to leave the list {}",
+                list_name
+            ),
+            JoinResult::BotError
+            | JoinResult::ApprovalRequested
+            | JoinResult::ApprovalPending
+            | JoinResult::ApprovalUnavailable
+            | JoinResult::ApprovalDenied(_) => {
+                format!("Failed to remove member from list {}", list_name)
+            }
+            JoinResult::RoleUnavailable => format!("Could not take the role of list {} away from you, ask a moderator to check the bot's role permissions", list_name),
+        }
+    }
+
+    async fn handle_join(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let member_id: UserId = command
+            .member
+            .as_ref()
+            .expect("Interaction not triggered by a member")
+            .user
+            .id;
+        let member_admin = Handler::can_manage_messages(command);
+        let duration = Handler::membership_duration(command);
+        let list_names: Vec<CommandDataOption> = command
+            .data
+            .options
+            .iter()
+            .filter(|option| option.kind() == CommandOptionType::String)
+            .cloned()
+            .collect();
+
+        let mut content = format!("Joining the following {} lists:", list_names.len());
+
+        let authority = match member_admin {
+            true => JoinAuthority::Moderator,
+            false => JoinAuthority::Member,
+        };
+        for list_name in list_names {
+            let list_name = list_name.value.as_str().unwrap();
+            let result = self
+                .add_member(guild_id, list_name, member_id, authority, duration, ctx)
+                .await;
+            content += "\n";
+            content += &Handler::describe_join(result, list_name, duration);
+        }
+        Handler::send_text(&content, command, ctx, true).await;
+    }
+
+    async fn handle_leave(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let member_id: UserId = command
+            .member
+            .as_ref()
+            .expect("Interaction not triggered by a member")
+            .user
+            .id;
+        let as_admin = Handler::can_manage_messages(command);
+        let list_names: Vec<CommandDataOption> = command.data.options.clone();
+
+        let mut content = format!("Leaving the following {} lists:", list_names.len());
+        for list_name in list_names {
+            if list_name.kind() != CommandOptionType::String {
+                continue;
+            }
+            let list_name = list_name.value.as_str().unwrap();
+
+            let result = self
+                .remove_member(guild_id, list_name, member_id, as_admin, ctx)
+                .await;
+            content += "\n";
+            content += &Handler::describe_leave(result, list_name);
+        }
+        Handler::send_text(&content, command, ctx, true).await;
+    }
+
+    async fn handle_create(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let list_name: &str = &command
+            .data
+            .options
+            .get(0)
+            .expect("No list name given")
+            .value
+            .as_str()
+            .expect("list name is not a valid str.");
+
+        let as_admin = Handler::can_manage_messages(command);
+        if !as_admin {
+            Handler::send_not_allowed(&command, &ctx).await;
+            return;
+        }
+
+        let mut data = ctx.data.write().await;
+        let BotData { database: db, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        let mut content = String::new();
+
+        if let Ok(mut x) = db.clone().lock() {
+            if let Some(taken) = Handler::describe_name_taken(&mut x, guild_id, list_name, None) {
+                content = taken;
+            } else if x.add_list(guild_id, list_name).is_some() {
+                content += format!("Creating list {}.", list_name).as_str();
+            } else {
+                content += "This list already exists.";
+            };
+        }
+        if content.len() == 0 {
+            content += "Failed to access database.";
+        }
+
+        Handler::send_text(&content, command, ctx, false).await;
+    }
+
+    async fn handle_remove(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let list_name: &str = &command
+            .data
+            .options
+            .get(0)
+            .expect("No list name given")
+            .value
+            .as_str()
+            .expect("list name is not a valid str.");
+
+        let as_admin = Handler::can_manage_messages(command);
+        if !as_admin {
+            Handler::send_not_allowed(&command, &ctx).await;
+            return;
+        }
+
+        let mut data = ctx.data.write().await;
+        let BotData { database: db, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        let mut content = format!("Removing list {}.", list_name);
+        let mut archived = None;
+
+        if let Ok(mut x) = db.lock() {
+            match x.get_list_id_by_name(list_name, guild_id) {
+                Some(id) => {
+                    x.archive_list(id, now).expect("list archival failed");
+                    content = format!(
+                        "Archived list {}, it will be deleted <t:{}:R> unless it is restored.",
+                        list_name,
+                        now + x.get_archive_retention(guild_id)
+                    );
+                    archived = Some(id);
+                }
+                None => {
+                    content += "List does not exist.";
+                }
+            };
+        }
+        drop(data);
+
+        let mut message = CreateInteractionResponseMessage::new().content(content);
+        if let Some(list_id) = archived {
+            message = message.components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                format!("list_restore:{}", list_id),
+            )
+            .label("Undo")
+            .style(ButtonStyle::Secondary)])]);
+        }
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(message))
+            .await
+            .expect("Failed to send text response, see error for details.");
+    }
+
+    /// Brings an archived list back, as long as none of its names were taken in the meantime.
+    fn restore_archived_list(x: &mut Database, guild_id: GuildId, list_id: ListId) -> String {
+        if x.get_list_guild(list_id).ok() != Some(guild_id) || !x.is_archived(list_id) {
+            return "This list is not archived anymore.".to_string();
+        }
+        // Archived lists keep their names reserved, so restoring can never clash with another list
+        let names = x.get_list_names(list_id);
+        x.restore_list(list_id).expect("list restoration failed");
+        format!("Restored list {}.", names.join(", "))
+    }
+
+    async fn handle_restore(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let list_name: &str = command
+            .data
+            .options
+            .first()
+            .and_then(|option| option.value.as_str())
+            .expect("No list name given");
+
+        if !Handler::can_manage_messages(command) {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+
+        let content = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let content = db.lock().ok().map(|mut x| {
+                match x.get_archived_list_id_by_name(list_name, guild_id) {
+                    Some(list_id) => Handler::restore_archived_list(&mut x, guild_id, list_id),
+                    None => format!("There is no archived list named {}.", list_name),
+                }
+            });
+            content
+        };
+        Handler::send_text(
+            &content.unwrap_or("Something went wrong.".to_string()),
+            command,
+            ctx,
+            false,
+        )
+        .await;
+    }
+
+    async fn autocomplete_restore(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        const SUGGESTIONS: usize = 5;
+        let mut suggestions: Vec<String> = Vec::new();
+        if let (Some(guild_id), Some(focused)) =
+            (autocomplete.guild_id, autocomplete.data.autocomplete())
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                suggestions = x.get_archived_list_names(guild_id, SUGGESTIONS, focused.value);
+            };
+        }
+
+        let mut resp = CreateAutocompleteResponse::new();
+        for suggestion in suggestions {
+            resp = resp.add_string_choice(&suggestion, &suggestion);
+        }
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+            .await
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn restore_list_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let (Some(guild_id), Some(Ok(list_id))) = (
+            component.guild_id,
+            component
+                .data
+                .custom_id
+                .split(':')
+                .nth(1)
+                .map(|id| id.parse::<u64>()),
+        ) else {
+            return;
+        };
+        let moderator = component.member.as_ref().is_some_and(|member| {
+            member
+                .permissions
+                .is_some_and(|p| p.contains(Permissions::MANAGE_MESSAGES))
+        });
+        if !moderator {
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content("Only moderators can restore lists.")
+                            .ephemeral(true),
+                    ),
+                )
+                .await
+                .ok();
+            return;
+        }
+
+        let content = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let content = db
+                .lock()
+                .ok()
+                .map(|mut x| Handler::restore_archived_list(&mut x, guild_id, list_id));
+            content
+        };
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content.unwrap_or("Something went wrong.".to_string()))
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    /// Explains why a list cannot take a name, when another list of the guild already uses it.
+    fn describe_name_taken(
+        x: &mut Database,
+        guild_id: GuildId,
+        name: &str,
+        list_id: Option<ListId>,
+    ) -> Option<String> {
+        match x.get_alias_owner(name, guild_id) {
+            Some((owner, _)) if Some(owner) == list_id => None,
+            Some((_, true)) => Some(format!(
+                "The name {} belongs to an archived list, restore it with /restore or pick another name.",
+                name
+            )),
+            Some((owner, false)) => Some(format!(
+                "The name {} is already used by the list {}.",
+                name,
+                x.get_list_names(owner).join(", ")
+            )),
+            None => None,
+        }
+    }
+
+    /// Explains why a list linked to a role cannot be merged or split: its members follow the
+    /// role, so moving them around in the list would be undone by the next member update.
+    fn describe_role_backed(x: &mut Database, list_id: ListId) -> Option<String> {
+        x.get_list_role(list_id).map(|role_id| {
+            format!(
+                "The list {} follows the role <@&{}>, unlink it with /link_role first.",
+                x.get_list_names(list_id).join(", "),
+                role_id
+            )
+        })
+    }
+
+    /// Moves members of a list to a new list, returning the message to show.
+    fn split_members(
+        x: &mut Database,
+        guild_id: GuildId,
+        list_id: ListId,
+        new_name: &str,
+        member_ids: &[UserId],
+    ) -> String {
+        if let Some(role_backed) = Handler::describe_role_backed(x, list_id) {
+            return role_backed;
+        }
+        match x
+            .split_list(list_id, new_name, member_ids)
+            .expect("Failed to split list")
+        {
+            Some((_, moved)) => format!(
+                "Created list {} with {} members of {}.",
+                new_name,
+                moved,
+                x.get_list_names(list_id).join(", ")
+            ),
+            None => Handler::describe_name_taken(x, guild_id, new_name, None)
+                .unwrap_or(format!("The name {} is already in use.", new_name)),
+        }
+    }
+
+    async fn handle_rename(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let option = |name: &str| {
+            command
+                .data
+                .options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+                .unwrap_or_default()
+        };
+        let (list_name, new_name) = (option("name"), option("new_name"));
+
+        if !Handler::can_manage_list(command, ctx, list_name).await {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+
+        let content = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let content = db.lock().ok().map(|mut x| {
+                let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) else {
+                    return format!("The list {} does not exist.", list_name);
+                };
+                if let Some(taken) =
+                    Handler::describe_name_taken(&mut x, guild_id, new_name, Some(list_id))
+                {
+                    return taken;
+                }
+                if x.rename_alias(list_id, list_name, new_name) {
+                    format!("Renamed {} to {}.", list_name, new_name)
+                } else {
+                    format!("{} is already a name of this list.", new_name)
+                }
+            });
+            content
+        };
+        Handler::send_text(
+            &content.unwrap_or("Something went wrong.".to_string()),
+            command,
+            ctx,
+            false,
+        )
+        .await;
+    }
+
+    async fn handle_merge(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        if !Handler::can_manage_messages(command) {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+        let option = |name: &str| {
+            command
+                .data
+                .options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+                .unwrap_or_default()
+        };
+        let (source_name, target_name) = (option("list"), option("into"));
+
+        let content = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let content = db.lock().ok().map(|mut x| {
+                match (
+                    x.get_list_id_by_name(source_name, guild_id),
+                    x.get_list_id_by_name(target_name, guild_id),
+                ) {
+                    (None, _) => format!("The list {} does not exist.", source_name),
+                    (_, None) => format!("The list {} does not exist.", target_name),
+                    (Some(source_id), Some(target_id)) if source_id == target_id => {
+                        format!("{} and {} are the same list.", source_name, target_name)
+                    }
+                    (Some(source_id), Some(target_id)) => {
+                        if let Some(role_backed) = Handler::describe_role_backed(&mut x, source_id)
+                            .or_else(|| Handler::describe_role_backed(&mut x, target_id))
+                        {
+                            return role_backed;
+                        }
+                        // Only the target's settings survive, say what is lost
+                        let source = x.get_list(source_id).expect("List disappeared");
+                        let target = x.get_list(target_id).expect("List disappeared");
+                        let mut dropped = vec![];
+                        if !source.description.is_empty() && source.description != target.description
+                        {
+                            dropped.push(format!("description \"{}\"", source.description));
+                        }
+                        if source.category.is_some() && source.category != target.category {
+                            dropped.push(format!(
+                                "category {}",
+                                source.category.unwrap_or_default()
+                            ));
+                        }
+                        match x
+                            .merge_lists(source_id, target_id)
+                            .expect("Failed to merge lists")
+                        {
+                            Some(moved) if dropped.is_empty() => format!(
+                                "Merged {} into {}, {} members were added.",
+                                source_name, target_name, moved
+                            ),
+                            Some(moved) => format!(
+                                "Merged {} into {}, {} members were added. Dropped the {} of {}.",
+                                source_name,
+                                target_name,
+                                moved,
+                                dropped.join(" and "),
+                                source_name
+                            ),
+                            None => format!(
+                                "Could not merge {} into {}, it would make the list include itself.",
+                                source_name, target_name
+                            ),
+                        }
+                    }
+                }
+            });
+            content
+        };
+        Handler::send_text(
+            &content.unwrap_or("Something went wrong.".to_string()),
+            command,
+            ctx,
+            false,
+        )
+        .await;
+    }
+
+    async fn handle_split(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        if !Handler::can_manage_messages(command) {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+        let option = |name: &str| {
+            command
+                .data
+                .options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+                .unwrap_or_default()
+        };
+        let (list_name, new_name) = (option("list"), option("name"));
+        let role = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "role")
+            .and_then(|option| option.value.as_role_id());
+
+        let outcome = {
+            let mut data = ctx.data.write().await;
+            let BotData {
+                database: db,
+                pending_splits,
+                ..
+            } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            let lists = db.lock().ok().map(|mut x| {
+                let list_id = x.get_list_id_by_name(list_name, guild_id);
+                (
+                    list_id,
+                    Handler::describe_name_taken(&mut x, guild_id, new_name, None),
+                    list_id.and_then(|list_id| Handler::describe_role_backed(&mut x, list_id)),
+                )
+            });
+            match lists {
+                None => Err("Something went wrong.".to_string()),
+                Some((None, _, _)) => Err(format!("The list {} does not exist.", list_name)),
+                Some((_, Some(taken), _)) => Err(taken),
+                Some((_, _, Some(role_backed))) => Err(role_backed),
+                Some((Some(list_id), None, None)) if role.is_some() => Ok(list_id),
+                Some((Some(list_id), None, None)) => {
+                    pending_splits.insert(
+                        command.id.get(),
+                        (
+                            list_id,
+                            new_name.to_string(),
+                            serenity::model::Timestamp::now().unix_timestamp() as u64,
+                        ),
+                    );
+                    Ok(list_id)
+                }
+            }
+        };
+
+        let list_id = match outcome {
+            Ok(list_id) => list_id,
+            Err(content) => {
+                Handler::send_text(&content, command, ctx, true).await;
+                return;
+            }
+        };
+
+        // Picking members one by one does not scale, a role moves everyone holding it at once
+        if let Some(role_id) = role {
+            command.defer_ephemeral(&ctx.http).await.unwrap();
+            let content = match guild_id
+                .members_iter(&ctx.http)
+                .try_collect::<Vec<Member>>()
+                .await
+            {
+                Err(_) => "Could not retrieve the members of this server, the list was not split."
+                    .to_string(),
+                Ok(members) => {
+                    let holders: Vec<UserId> = members
+                        .iter()
+                        .filter(|member| member.roles.contains(&role_id))
+                        .map(|member| member.user.id)
+                        .collect();
+                    let data = ctx.data.read().await;
+                    let BotData { database: db, .. } = data
+                        .get::<DB>()
+                        .expect("Could not find database in bot data");
+                    let content = db.lock().ok().map(|mut x| {
+                        Handler::split_members(&mut x, guild_id, list_id, new_name, &holders)
+                    });
+                    content.unwrap_or("Something went wrong.".to_string())
+                }
+            };
+            command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+                .await
+                .expect("Failure communicating with discord api");
+            return;
+        }
+
+        let select_menu = CreateSelectMenu::new(
+            format!("list_split:{}", command.id),
+            serenity::all::CreateSelectMenuKind::User {
+                default_users: None,
+            },
+        )
+        .placeholder(format!("Members of {} to move to {}", list_name, new_name))
+        .min_values(1)
+        .max_values(25);
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!(
+                            "Pick the members of {} that move to the new list {}, or use the role option of /split to move more than 25 at once.",
+                            list_name, new_name
+                        ))
+                        .select_menu(select_menu)
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .expect("Failed to send split menu");
+    }
+
+    async fn split_list_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let Some(interaction_id) = component
+            .data
+            .custom_id
+            .strip_prefix("list_split:")
+            .and_then(|id| id.parse::<u64>().ok())
+        else {
+            return;
+        };
+        let ComponentInteractionDataKind::UserSelect { ref values } = component.data.kind else {
+            return;
+        };
+        let Some(guild_id) = component.guild_id else {
+            return;
+        };
+
+        let content = {
+            let mut data = ctx.data.write().await;
+            let BotData {
+                database: db,
+                pending_splits,
+                ..
+            } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            match pending_splits.remove(&interaction_id) {
+                None => "This split has expired, please use /split again.".to_string(),
+                Some((list_id, new_name, _)) => {
+                    let content = db.lock().ok().map(|mut x| {
+                        Handler::split_members(&mut x, guild_id, list_id, &new_name, values)
+                    });
+                    content.unwrap_or("Something went wrong.".to_string())
+                }
+            }
+        };
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    async fn handle_alias(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let list_name: &str = &command
+            .data
+            .options
+            .get(0)
+            .expect("No list name given")
+            .value
+            .as_str()
+            .expect("list name is not a valid str.");
+        let list_alias: &str = &command
+            .data
+            .options
+            .get(1)
+            .expect("No list alias given")
+            .value
+            .as_str()
+            .expect("list alias is not a valid str.");
+
+        if !Handler::can_manage_list(command, ctx, list_name).await {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+        let mut data = ctx.data.write().await;
+        let BotData { database: db, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        let mut content = String::new();
+        if let Ok(mut x) = db.clone().lock() {
+            let res_id = x.get_list_id_by_name(list_name, guild_id);
+
+            if let Some(id) = res_id {
+                if let Some(taken) =
+                    Handler::describe_name_taken(&mut x, guild_id, list_alias, Some(id))
+                {
+                    content = taken;
+                } else if x.add_alias_inline(id, list_alias) {
+                    content = format!("Added alias {} to list {}.", list_alias, list_name);
+                } else {
+                    content = format!("{} is already a name of this list.", list_alias);
+                }
+            } else {
+                content = format!("There is no list named {} to alias to.", list_alias);
+            }
+        }
+
+        Handler::send_text(&content, command, ctx, false).await;
+    }
+
+    async fn handle_remove_alias(&self, command: &CommandInteraction, ctx: &Context) {
+        let guild_id: GuildId = command.guild_id.expect("No guild data found");
+        let list_name: &str = &command
+            .data
+            .options
+            .get(0)
+            .expect("No list name given")
+            .value
+            .as_str()
+            .expect("list name is not a valid str.");
+
+        if !Handler::can_manage_list(command, ctx, list_name).await {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+        let mut data = ctx.data.write().await;
+        let BotData { database: db, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        let mut content = String::new();
+        if let Ok(mut x) = db.clone().lock() {
+            if let Some(id) = x.get_list_id_by_name(list_name, guild_id) {
+                if x.get_list_names(id).len() > 1 {
+                    x.remove_alias(None, id, list_name).unwrap();
+                    content = format!("Removed alias {}.", list_name);
+                } else {
+                    content = format!("You cannot remove the last alias of a list.")
+                }
+            } else {
+                content = format!("There is no list named {} to alias to.", list_name);
+            }
+        }
+
+        Handler::send_text(&content, command, ctx, false).await;
+    }
+
+    async fn autocomplete_alias(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        let member = autocomplete.member.as_ref().unwrap();
+        let member_admin = member
+            .permissions
+            .unwrap()
+            .contains(Permissions::MANAGE_MESSAGES);
+        let guild_id = autocomplete.guild_id.expect("No guild data found");
+
+        let mut filter = "";
+        for field in &autocomplete.data.options {
+            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
+                if field.name == "name" && kind == CommandOptionType::String {
+                    filter = value;
+                }
+            }
+        }
+        let mut suggestions = Vec::new();
+
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            alias_indexes,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+
+        if let Ok(mut x) = db.lock() {
+            // Members that are not moderators only get the lists they manage
+            let managed: Vec<String> = if member_admin {
+                vec![]
+            } else {
+                x.get_managed_list_names(guild_id, member.user.id)
+            };
+            suggestions =
+                Handler::ranked_suggestions(&mut x, alias_indexes, guild_id, filter, |alias| {
+                    member_admin || managed.contains(&alias.name)
+                });
+        }
+
+        let resp = Handler::list_choices(suggestions);
 
         autocomplete
             .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
             .await
-            .expect("Failure communicating with discord api");
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn handle_info(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let list_name = command
+            .data
+            .options
+            .first()
+            .and_then(|option| option.value.as_str())
+            .unwrap_or_default();
+        let member_admin = Handler::can_manage_messages(command);
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+
+        let mut profile = None;
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                // Hidden lists, such as pending proposals, are only shown to moderators
+                if let Some(list) = x
+                    .get_list_id_by_name(list_name, guild_id)
+                    .and_then(|list_id| x.get_list(list_id))
+                    .filter(|list| list.visible || member_admin)
+                {
+                    let names = x.get_list_names(list.id);
+                    let members = x.get_members_in_list(list.id).len();
+                    let reached = x.get_effective_members_in_list(list.id).len();
+                    let (_, _, guild_cooldown) = x.get_guild_ping_data(guild_id);
+                    let cooldown = if list.cooldown == -1 {
+                        guild_cooldown
+                    } else {
+                        list.cooldown as u64
+                    };
+                    let last_ping = x.get_list_last_ping(list.id);
+
+                    let mut embed = CreateEmbed::default()
+                        .color((31, 127, 255))
+                        .title(names.first().cloned().unwrap_or_default())
+                        .description(if list.description.is_empty() {
+                            "No description."
+                        } else {
+                            &list.description
+                        })
+                        .field("Names", names.join(", "), false)
+                        .field(
+                            "Members",
+                            if reached != members {
+                                format!("{} ({} through included lists)", members, reached)
+                            } else {
+                                members.to_string()
+                            },
+                            true,
+                        )
+                        .field(
+                            "Category",
+                            list.category.clone().unwrap_or("None".to_string()),
+                            true,
+                        )
+                        .field(
+                            "Joining",
+                            if x.get_requires_approval(list.id) {
+                                format!("{}, needs approval", list.join_permission)
+                            } else {
+                                list.join_permission.to_string()
+                            },
+                            true,
+                        )
+                        .field("Pinging", list.ping_permission.to_string(), true)
+                        .field(
+                            "Cooldown",
+                            if list.cooldown == -1 {
+                                format!("{} seconds (server default)", cooldown)
+                            } else {
+                                format!("{} seconds", cooldown)
+                            },
+                            true,
+                        )
+                        .field(
+                            "Last ping",
+                            if last_ping == 0 {
+                                "Never".to_string()
+                            } else {
+                                format!("<t:{}:R>", last_ping)
+                            },
+                            true,
+                        )
+                        .field(
+                            "Pingable again",
+                            if last_ping != 0 && last_ping + cooldown >= now {
+                                format!("<t:{}:R>", last_ping + cooldown + 1)
+                            } else {
+                                "Now".to_string()
+                            },
+                            true,
+                        );
+
+                    if member_admin {
+                        let managers = x.get_list_managers(list.id);
+                        let (_, timeout, threshold) = x.get_propose_settings(guild_id);
+                        embed = embed
+                            .field(
+                                "Managers",
+                                if managers.is_empty() {
+                                    "None".to_string()
+                                } else {
+                                    managers
+                                        .iter()
+                                        .map(|user_id| format!("<@{}>", user_id))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                },
+                                false,
+                            )
+                            .field("Hidden", (!list.visible).to_string(), true)
+                            .field(
+                                "Proposal",
+                                match x.get_proposal_data(list.id) {
+                                    ProposalStatus::ACTIVE(_, votes, timestamp, ..) => format!(
+                                        "{} of {} votes, ends <t:{}:R>",
+                                        votes,
+                                        threshold,
+                                        timestamp + timeout
+                                    ),
+                                    _ => "None".to_string(),
+                                },
+                                true,
+                            );
+                    }
+                    let joined = x.has_member(command.user.id, list.id);
+                    profile = Some((embed, list.id, joined));
+                }
+            };
+        }
+
+        let Some((embed, list_id, joined)) = profile else {
+            Handler::send_text(
+                &format!("The list {} does not exist.", list_name),
+                command,
+                ctx,
+                true,
+            )
+            .await;
+            return;
+        };
+        let button = if joined {
+            CreateButton::new(format!("list_leave:{}", list_id))
+                .label("Leave")
+                .style(ButtonStyle::Danger)
+        } else {
+            CreateButton::new(format!("list_join:{}", list_id))
+                .label("Join")
+                .style(ButtonStyle::Primary)
+        };
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .embed(embed)
+                        .components(vec![CreateActionRow::Buttons(vec![button])])
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .expect("Failed to send list info");
     }
 
-    async fn add_member(
+    async fn join_leave_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let (action, list_id) = component.data.custom_id.split_once(':').unwrap_or_default();
+        let (Some(guild_id), Ok(list_id)) = (component.guild_id, list_id.parse::<u64>()) else {
+            return;
+        };
+        let member_admin = component.member.as_ref().is_some_and(|member| {
+            member
+                .permissions
+                .is_some_and(|p| p.contains(Permissions::MANAGE_MESSAGES))
+        });
+
+        let list_name = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let list_name = db
+                .lock()
+                .ok()
+                .and_then(|mut x| x.get_list_names(list_id).first().cloned());
+            list_name
+        };
+        let content = match list_name {
+            None => "This list no longer exists.".to_string(),
+            Some(list_name) if action == "list_leave" => Handler::describe_leave(
+                self.remove_member(guild_id, &list_name, component.user.id, member_admin, ctx)
+                    .await,
+                &list_name,
+            ),
+            Some(list_name) => Handler::describe_join(
+                self.add_member(
+                    guild_id,
+                    &list_name,
+                    component.user.id,
+                    match member_admin {
+                        true => JoinAuthority::Moderator,
+                        false => JoinAuthority::Member,
+                    },
+                    None,
+                    ctx,
+                )
+                .await,
+                &list_name,
+                None,
+            ),
+        };
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    /// A page of the lists a member is on with their descriptions, a menu to switch pages and
+    /// one to leave the lists shown.
+    async fn compose_get(
         &self,
         guild_id: GuildId,
-        list_name: &str,
         member_id: UserId,
-        as_admin: bool,
+        page: usize,
         ctx: &Context,
-    ) -> JoinResult {
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-
-        if let Ok(mut x) = db.clone().lock() {
-            let res_list_id = x.get_list_id_by_name(list_name, guild_id);
-            if let Some(list_id) = res_list_id {
-                let (_, list_join_permission, _) = x.get_list_permissions(list_id);
-                if list_join_permission == PERMISSION::DENY && !as_admin {
-                    return JoinResult::MissingPerms;
+    ) -> (CreateEmbed, Vec<CreateActionRow>) {
+        const PAGESIZE: usize = 10;
+        const DESCRIPTION_LENGTH: usize = 200;
+        // The list to leave, None for lists the member is on through another list
+        let mut entries: Vec<(Option<ListId>, String, String)> = Vec::new();
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                for list_id in x.get_lists_with_member(guild_id, member_id).unwrap() {
+                    entries.push((
+                        Some(list_id),
+                        x.get_list_names(list_id).join(", "),
+                        x.get_list(list_id)
+                            .map(|list| list.description)
+                            .unwrap_or_default(),
+                    ));
                 }
-                return x.add_member(member_id, list_id);
-            } else {
-                return JoinResult::ListDoesNotExist;
+                for (list_id, via) in x
+                    .get_inherited_lists_with_member(guild_id, member_id)
+                    .unwrap()
+                {
+                    let via_name = x.get_list_names(via).into_iter().next().unwrap_or_default();
+                    entries.push((
+                        None,
+                        format!(
+                            "{} (via {})",
+                            x.get_list_names(list_id).join(", "),
+                            via_name
+                        ),
+                        x.get_list(list_id)
+                            .map(|list| list.description)
+                            .unwrap_or_default(),
+                    ));
+                }
+            };
+        }
+
+        let mut embed = CreateEmbed::default();
+        if entries.is_empty() {
+            embed = embed.color((255, 0, 0)).title("You are not on any lists.");
+            return (embed, vec![]);
+        }
+        let page_count = 1 + (entries.len() - 1) / PAGESIZE;
+        let page = min(page, page_count - 1);
+        let shown = &entries[page * PAGESIZE..min(entries.len(), (page + 1) * PAGESIZE)];
+
+        let mut lines: Vec<String> = Vec::new();
+        for (index, (list_id, names, description)) in shown.iter().enumerate() {
+            if list_id.is_none() && (index == 0 || shown[index - 1].0.is_some()) {
+                lines.push("**Through the lists above**".to_string());
             }
+            let description = if description.chars().count() > DESCRIPTION_LENGTH {
+                format!(
+                    "{}...",
+                    description
+                        .chars()
+                        .take(DESCRIPTION_LENGTH)
+                        .collect::<String>()
+                )
+            } else {
+                description.clone()
+            };
+            lines.push(match description.as_str() {
+                "" => format!("- {}", names),
+                _ => format!("- {}\n    {}", names, description),
+            });
+        }
+        embed = embed
+            .color((127, 255, 160))
+            .title(format!(
+                "You are in {} lists, showing {}-{}:",
+                entries.len(),
+                page * PAGESIZE + 1,
+                page * PAGESIZE + shown.len()
+            ))
+            .description(lines.join("\n"));
+
+        let mut action_rows = Vec::new();
+        if page_count > 1 {
+            // Discord allows 25 options, so only offer the pages around the current one.
+            let first_page = page.saturating_sub(12);
+            let select_menu_options = (first_page..min(page_count, first_page + 25))
+                .map(|page_index| {
+                    CreateSelectMenuOption::new(
+                        format!(
+                            "{}:{}",
+                            page_index * PAGESIZE + 1,
+                            min(entries.len(), (page_index + 1) * PAGESIZE)
+                        ),
+                        page_index.to_string(),
+                    )
+                })
+                .collect::<Vec<CreateSelectMenuOption>>();
+            let select_menu = CreateSelectMenu::new(
+                "get_page",
+                serenity::all::CreateSelectMenuKind::String {
+                    options: select_menu_options,
+                },
+            )
+            .placeholder("Navigate between pages");
+            action_rows.push(CreateActionRow::SelectMenu(select_menu));
+        }
+        let leave_options = shown
+            .iter()
+            .filter_map(|(list_id, names, _)| {
+                list_id.map(|list_id| {
+                    CreateSelectMenuOption::new(
+                        names.chars().take(100).collect::<String>(),
+                        list_id.to_string(),
+                    )
+                })
+            })
+            .collect::<Vec<CreateSelectMenuOption>>();
+        if !leave_options.is_empty() {
+            let option_count = leave_options.len() as u8;
+            let select_menu = CreateSelectMenu::new(
+                format!("get_leave:{}", page),
+                serenity::all::CreateSelectMenuKind::String {
+                    options: leave_options,
+                },
+            )
+            .placeholder("Leave lists")
+            .min_values(1)
+            .max_values(option_count);
+            action_rows.push(CreateActionRow::SelectMenu(select_menu));
         }
-        return JoinResult::BotError;
+        (embed, action_rows)
     }
 
-    async fn remove_member(
-        &self,
-        guild_id: GuildId,
-        list_name: &str,
-        member_id: UserId,
-        as_admin: bool,
-        ctx: &Context,
-    ) -> JoinResult {
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
+    async fn handle_get(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
 
-        if let Ok(mut x) = db.clone().lock() {
-            if let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) {
-                let (_, list_join_permission, _) = x.get_list_permissions(list_id);
-                if list_join_permission == PERMISSION::DENY && !as_admin {
-                    return JoinResult::MissingPerms;
-                }
-                if x.remove_member(member_id, list_id)
-                    .expect("Failed to remove membership.")
-                {
-                    return JoinResult::Succes;
-                } else {
-                    return JoinResult::AlreadyMember;
-                };
-            } else {
-                return JoinResult::ListDoesNotExist;
-            }
+        let (embed, action_rows) = self.compose_get(guild_id, command.user.id, 0, ctx).await;
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .embed(embed)
+                        .components(action_rows)
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .expect("Failed to send list overview");
+    }
+
+    async fn get_page_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let Some(guild_id) = component.guild_id else {
+            return;
+        };
+        let mut page = 0;
+        if let ComponentInteractionDataKind::StringSelect { ref values } = component.data.kind {
+            page = values
+                .first()
+                .and_then(|f| f.parse::<usize>().ok())
+                .unwrap_or(0);
+        }
+
+        let (embed, action_rows) = self
+            .compose_get(guild_id, component.user.id, page, ctx)
+            .await;
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(embed)
+                        .components(action_rows),
+                ),
+            )
+            .await
+            .ok();
+    }
+
+    async fn get_leave_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let Some(guild_id) = component.guild_id else {
+            return;
+        };
+        let ComponentInteractionDataKind::StringSelect { ref values } = component.data.kind else {
+            return;
+        };
+        let page = component
+            .data
+            .custom_id
+            .strip_prefix("get_leave:")
+            .and_then(|page| page.parse::<usize>().ok())
+            .unwrap_or(0);
+        let as_admin = component.member.as_ref().is_some_and(|member| {
+            member
+                .permissions
+                .is_some_and(|p| p.contains(Permissions::MANAGE_MESSAGES))
+        });
+
+        let list_names: Vec<String> = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let list_names = db.lock().ok().map(|mut x| {
+                values
+                    .iter()
+                    .filter_map(|value| value.parse::<u64>().ok())
+                    .filter_map(|list_id| x.get_list_names(list_id).first().cloned())
+                    .collect()
+            });
+            list_names.unwrap_or_default()
+        };
+
+        let mut results = Vec::new();
+        for list_name in list_names {
+            let result = self
+                .remove_member(guild_id, &list_name, component.user.id, as_admin, ctx)
+                .await;
+            results.push(Handler::describe_leave(result, &list_name));
         }
-        return JoinResult::BotError;
+
+        let (embed, action_rows) = self
+            .compose_get(guild_id, component.user.id, page, ctx)
+            .await;
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(results.join("\n"))
+                        .embed(embed)
+                        .components(action_rows),
+                ),
+            )
+            .await
+            .ok();
     }
 
-    async fn handle_join(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let member_id: UserId = command
-            .member
-            .as_ref()
-            .expect("Interaction not triggered by a member")
-            .user
-            .id;
-        let member_admin = Handler::can_manage_messages(command);
-        let list_names: Vec<CommandDataOption> = command.data.options.clone();
+    async fn handle_add(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
 
-        let mut content = format!("Joining the following {} lists:", list_names.len());
+        let Some(CommandDataOptionValue::User(member_id)) = command
+            .data
+            .options
+            .iter()
+            .find(|x| x.name == "user")
+            .and_then(|cdo| Some(&cdo.value))
+        else {
+            Handler::send_text("Missing user parameter.", command, ctx, true).await;
+            return;
+        };
+
+        let duration = Handler::membership_duration(command);
+        let list_names: Vec<&str> = command
+            .data
+            .options
+            .iter()
+            .filter(|option| option.kind() == CommandOptionType::String)
+            .map(|option| option.value.as_str().unwrap())
+            .collect();
+
+        let mut content = format!(
+            "Attempting to add user with id {} to {} lists:",
+            member_id,
+            list_names.len()
+        );
 
+        let authority = match Handler::can_manage_messages(command) {
+            true => JoinAuthority::Moderator,
+            false => JoinAuthority::Manager,
+        };
         for list_name in list_names {
-            if list_name.kind() != CommandOptionType::String {
+            if !Handler::can_manage_list(command, ctx, list_name).await {
+                content += format!("\nYou do not manage the list {}.", list_name).as_str();
                 continue;
             }
-            let list_name = list_name.value.as_str().unwrap();
             match self
-                .add_member(guild_id, list_name, member_id, member_admin, ctx)
+                .add_member(guild_id, list_name, *member_id, authority, duration, ctx)
                 .await
             {
                 JoinResult::AlreadyMember => {
-                    content += format!("\nYou already joined the list {}", list_name).as_str();
+                    content += format!("\nUser was already in list {}", list_name).as_str();
                 }
                 JoinResult::Succes => {
-                    content += format!("\nAdded to list {}", list_name).as_str();
+                    content += format!(
+                        "\nAdded to list {}{}",
+                        list_name,
+                        Handler::describe_expiry(duration)
+                    )
+                    .as_str();
                 }
                 JoinResult::ListDoesNotExist => {
-                    content += format!("\nThe list {} does not exist", list_name).as_str();
+                    content += format!("\nList {} does not exist", list_name).as_str();
                 }
                 JoinResult::MissingPerms => {
                     content += format!(
-                        "\nYou do not have permission to join the list {}.",
+                        "\nYou do not have permission to add user to list {}.",
                         list_name
                     )
                     .as_str();
                 }
-                JoinResult::BotError => {
-                    content += format!(
-                        "\nSomething went wrong trying to join the \"{}\" list.",
-                        list_name
-                    )
-                    .as_str();
+                JoinResult::BotError
+                | JoinResult::ApprovalRequested
+                | JoinResult::ApprovalPending
+                | JoinResult::ApprovalUnavailable
+                | JoinResult::ApprovalDenied(_) => {
+                    content +=
+                        format!("\nAn error occured adding user to list {}", list_name).as_str();
+                }
+                JoinResult::RoleUnavailable => {
+                    content += format!("\nCould not give the user the role of list {}", list_name)
+                        .as_str();
                 }
             }
         }
-        Handler::send_text(&content, command, ctx, true).await;
+        Handler::send_text(&content, command, ctx, false).await;
     }
 
-    async fn handle_leave(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let member_id: UserId = command
-            .member
-            .as_ref()
-            .expect("Interaction not triggered by a member")
-            .user
-            .id;
-        let as_admin = Handler::can_manage_messages(command);
+    async fn handle_kick(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+
+        let member_id = command
+            .data
+            .options
+            .iter()
+            .find(|x| x.name == "user")
+            .unwrap()
+            .value
+            .as_user_id()
+            .expect("Not given a valid user");
+
         let list_names: Vec<CommandDataOption> = command.data.options.clone();
 
-        let mut content = format!("Leaving the following {} lists:", list_names.len());
+        let mut content = format!(
+            "Attempting to remove user with id {} from {} lists:",
+            member_id,
+            list_names.len() - 1
+        );
         for list_name in list_names {
-            if list_name.kind() != CommandOptionType::String {
+            if list_name.name == "user" {
+                continue;
+            };
+            let list_name_str = list_name.value.as_str().unwrap();
+            if !Handler::can_manage_list(command, ctx, list_name_str).await {
+                content += format!("\nYou do not manage the list {}.", list_name_str).as_str();
                 continue;
             }
-            let list_name = list_name.value.as_str().unwrap();
 
             match self
-                .remove_member(guild_id, list_name, member_id, as_admin, ctx)
+                .remove_member(guild_id, list_name_str, member_id, true, ctx)
                 .await
             {
                 JoinResult::Succes => {
-                    content += format!("\nRemoved from list {}", list_name).as_str();
+                    content += format!("\nRemoved from list {}", list_name_str).as_str();
                 }
                 JoinResult::AlreadyMember => {
-                    content += format!("\nYou were not in the list {}", list_name).as_str();
+                    content +=
+                        format!("\nThis person was not in the list {}", list_name_str).as_str();
                 }
                 JoinResult::ListDoesNotExist => {
-                    content += format!("\nThe list {} does not exist", list_name).as_str();
+                    content += format!("\nThe list {} does not exist", list_name_str).as_str();
                 }
                 JoinResult::MissingPerms => {
                     content += format!(
-                        "\nYou do not have permission to leave the list {}",
-                        list_name
+                        "\nYou do not have permission to remove users from the list {}",
+                        list_name_str
                     )
                     .as_str();
                 }
-                JoinResult::BotError => {
+                JoinResult::BotError
+                | JoinResult::ApprovalRequested
+                | JoinResult::ApprovalPending
+                | JoinResult::ApprovalUnavailable
+                | JoinResult::ApprovalDenied(_) => {
                     content +=
-                        format!("\nFailed to remove member from list {}", list_name).as_str();
+                        format!("\nFailed to remove member from list {}", list_name_str).as_str();
+                }
+                JoinResult::RoleUnavailable => {
+                    content += format!(
+                        "\nCould not take the role of list {} away from the user",
+                        list_name_str
+                    )
+                    .as_str();
                 }
             }
         }
-        Handler::send_text(&content, command, ctx, true).await;
-    }
-
-    async fn handle_create(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let list_name: &str = &command
-            .data
-            .options
-            .get(0)
-            .expect("No list name given")
-            .value
-            .as_str()
-            .expect("list name is not a valid str.");
-
-        let as_admin = Handler::can_manage_messages(command);
-        if !as_admin {
-            Handler::send_not_allowed(&command, &ctx).await;
-            return;
-        }
-
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-
-        let mut content = String::new();
-
-        if let Ok(mut x) = db.clone().lock() {
-            if let Some(_) = x.add_list(guild_id, &list_name.to_string()) {
-                content += format!("Creating list {}.", list_name).as_str();
-            } else {
-                content += "This list already exists.";
-            };
-        }
-        if content.len() == 0 {
-            content += "Failed to access database.";
-        }
 
         Handler::send_text(&content, command, ctx, false).await;
     }
 
-    async fn handle_remove(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let list_name: &str = &command
-            .data
-            .options
-            .get(0)
-            .expect("No list name given")
-            .value
-            .as_str()
-            .expect("list name is not a valid str.");
-
-        let as_admin = Handler::can_manage_messages(command);
-        if !as_admin {
-            Handler::send_not_allowed(&command, &ctx).await;
-            return;
+    /// Whether the invoker may give `role_id` to others: they need Manage Roles and the role has
+    /// to be below their highest one, the everyone role and integration roles never qualify.
+    async fn check_assignable_role(
+        command: &CommandInteraction,
+        ctx: &Context,
+        role_id: RoleId,
+    ) -> Result<(), &'static str> {
+        let guild_id = command.guild_id.expect("No guild data found");
+        let member = command
+            .member
+            .as_ref()
+            .expect("Interaction not triggered by a member");
+        if !member
+            .permissions
+            .is_some_and(|p| p.contains(Permissions::MANAGE_ROLES))
+        {
+            return Err("You need the Manage Roles permission to link a role.");
         }
-
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-
-        let mut content = format!("Removing list {}.", list_name);
-
-        if let Ok(mut x) = db.clone().lock() {
-            match x.get_list_id_by_name(list_name, guild_id) {
-                Some(id) => {
-                    x.remove_list(id).expect("list removal failed"); // Fails HARD on proposals
-                    content = "Removed list.".to_string();
-                }
-                None => {
-                    content += "List does not exist.";
-                    ()
-                }
-            };
+        let Ok(guild) = guild_id.to_partial_guild(&ctx.http).await else {
+            return Err("Could not retrieve the roles of this server, the list was not linked.");
+        };
+        let Some(role) = guild.roles.get(&role_id) else {
+            return Err("This role does not exist.");
+        };
+        if role_id.get() == guild_id.get() || role.managed {
+            return Err("The everyone role and roles managed by an integration cannot be linked.");
         }
-
-        Handler::send_text(&content, command, ctx, false).await;
+        let highest = member
+            .roles
+            .iter()
+            .filter_map(|id| guild.roles.get(id))
+            .map(|role| role.position)
+            .max()
+            .unwrap_or(0);
+        if guild.owner_id != member.user.id && role.position >= highest {
+            return Err("You can only link roles below your highest role.");
+        }
+        Ok(())
     }
 
-    async fn handle_alias(&self, command: &CommandInteraction, ctx: &Context) {
+    /// Links a list to a role and reconciles the two, reporting the members that could not be
+    /// brought in sync.
+    async fn handle_link_role(&self, command: &CommandInteraction, ctx: &Context) {
         let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let list_name: &str = &command
-            .data
-            .options
-            .get(0)
-            .expect("No list name given")
-            .value
-            .as_str()
-            .expect("list name is not a valid str.");
-        let list_alias: &str = &command
-            .data
-            .options
-            .get(1)
-            .expect("No list alias given")
-            .value
-            .as_str()
-            .expect("list alias is not a valid str.");
-
-        let as_admin = Handler::can_manage_messages(command);
-        if !as_admin {
+        if !Handler::can_manage_messages(command) {
             Handler::send_not_allowed(command, ctx).await;
             return;
         }
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-
-        let mut content = String::new();
-        if let Ok(mut x) = db.clone().lock() {
-            let res_id = x.get_list_id_by_name(list_name, guild_id);
-
-            if let Some(id) = res_id {
-                x.add_alias_inline(id, list_alias);
-                content = format!("Added alias {} to list {}.", list_alias, list_name);
-            } else {
-                content = format!("There is no list named {} to alias to.", list_alias);
+        let mut list_name = "";
+        let mut role: Option<RoleId> = None;
+        let mut prefer = "merge";
+        for option in &command.data.options {
+            match (option.name.as_str(), &option.value) {
+                ("list", CommandDataOptionValue::String(value)) => list_name = value,
+                ("role", CommandDataOptionValue::Role(value)) => role = Some(*value),
+                ("prefer", CommandDataOptionValue::String(value)) => prefer = value,
+                _ => (),
             }
         }
 
-        Handler::send_text(&content, command, ctx, false).await;
-    }
-
-    async fn handle_remove_alias(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.expect("No guild data found");
-        let list_name: &str = &command
-            .data
-            .options
-            .get(0)
-            .expect("No list name given")
-            .value
-            .as_str()
-            .expect("list name is not a valid str.");
-
-        let as_admin = Handler::can_manage_messages(command);
-        if !as_admin {
-            Handler::send_not_allowed(command, ctx).await;
+        let list_id = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let list_id = db
+                .lock()
+                .ok()
+                .and_then(|mut x| x.get_list_id_by_name(list_name, guild_id));
+            list_id
+        };
+        let Some(list_id) = list_id else {
+            let content = format!("The list {} does not exist", list_name);
+            Handler::send_text(&content, command, ctx, true).await;
             return;
-        }
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
+        };
 
-        let mut content = String::new();
-        if let Ok(mut x) = db.clone().lock() {
-            if let Some(id) = x.get_list_id_by_name(list_name, guild_id) {
-                if x.get_list_names(id).len() > 1 {
-                    x.remove_alias(None, id, list_name).unwrap();
-                    content = format!("Removed alias {}.", list_name);
-                } else {
-                    content = format!("You cannot remove the last alias of a list.")
-                }
-            } else {
-                content = format!("There is no list named {} to alias to.", list_name);
+        // Anyone allowed to join the list receives the role, so it has to be one the invoker
+        // could hand out themselves
+        if let Some(role_id) = role {
+            if let Err(reason) = Handler::check_assignable_role(command, ctx, role_id).await {
+                Handler::send_text(reason, command, ctx, true).await;
+                return;
             }
         }
 
-        Handler::send_text(&content, command, ctx, false).await;
-    }
+        let Some(role_id) = role else {
+            {
+                let data = ctx.data.read().await;
+                let BotData { database: db, .. } = data
+                    .get::<DB>()
+                    .expect("Could not find database in bot data");
+                if let Ok(mut x) = db.lock() {
+                    x.set_list_role(list_id, None);
+                };
+            }
+            let content = format!("The list {} is no longer linked to a role.", list_name);
+            Handler::send_text(&content, command, ctx, false).await;
+            return;
+        };
 
-    async fn autocomplete_alias(&self, autocomplete: &CommandInteraction, ctx: &Context) {
-        let member = autocomplete.member.as_ref().unwrap();
-        let member_admin = member
-            .permissions
-            .unwrap()
-            .contains(Permissions::MANAGE_MESSAGES);
-        if !member_admin {
-            autocomplete
-                .create_response(
+        // Fetching every member can take a while on larger servers
+        command.defer(&ctx.http).await.unwrap();
+        let Ok(members) = guild_id
+            .members_iter(&ctx.http)
+            .try_collect::<Vec<Member>>()
+            .await
+        else {
+            command
+                .edit_response(
                     &ctx.http,
-                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new()),
+                    EditInteractionResponse::new().content(
+                        "Could not retrieve the members of this server, the list was not linked.",
+                    ),
                 )
                 .await
                 .expect("Failure communicating with discord api");
             return;
-        }
-        let guild_id = autocomplete.guild_id.expect("No guild data found");
-        const SUGGESTIONS: usize = 5;
+        };
+        let present: BTreeSet<UserId> = members.iter().map(|m| m.user.id).collect();
+        let holders: BTreeSet<UserId> = members
+            .iter()
+            .filter(|m| m.roles.contains(&role_id))
+            .map(|m| m.user.id)
+            .collect();
 
-        let mut filter = "";
-        for field in &autocomplete.data.options {
-            if let CommandDataOptionValue::Autocomplete { kind, ref value } = field.value {
-                if field.name == "name" && kind == CommandOptionType::String {
-                    filter = value;
+        let list_members: BTreeSet<UserId> = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let mut x = db.lock().expect("Could not get database access.");
+            x.set_list_role(list_id, Some(role_id));
+            BTreeSet::from_iter(x.get_members_in_list(list_id))
+        };
+        let only_role: Vec<UserId> = holders.difference(&list_members).cloned().collect();
+        let only_list: Vec<UserId> = list_members
+            .intersection(&present)
+            .filter(|id| !holders.contains(id))
+            .cloned()
+            .collect();
+
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            let mut x = db.lock().expect("Could not get database access.");
+            if prefer != "list" {
+                for member_id in &only_role {
+                    x.add_member(*member_id, list_id);
+                }
+            }
+            if prefer == "role" {
+                for member_id in &only_list {
+                    x.remove_member(*member_id, list_id)
+                        .expect("Failed to remove membership.");
                 }
             }
         }
-        let mut aliases: Vec<String> = Vec::new();
-
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
 
-        if let Ok(mut x) = db.clone().lock() {
-            aliases = x.get_list_aliases_by_search(guild_id, 0, SUGGESTIONS, filter, true)
+        let mut failed: Vec<UserId> = vec![];
+        if prefer != "role" {
+            for member_id in &only_list {
+                if ctx
+                    .http
+                    .add_member_role(guild_id, *member_id, role_id, Some("Linked list member"))
+                    .await
+                    .is_err()
+                {
+                    failed.push(*member_id);
+                }
+            }
         }
-
-        let mut resp = CreateAutocompleteResponse::new();
-        for list in aliases {
-            resp = resp.add_string_choice(&list, &list);
+        if prefer == "list" {
+            for member_id in &only_role {
+                if ctx
+                    .http
+                    .remove_member_role(guild_id, *member_id, role_id, Some("Not on linked list"))
+                    .await
+                    .is_err()
+                {
+                    failed.push(*member_id);
+                }
+            }
         }
 
-        autocomplete
-            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+        let mut content = format!(
+            "Linked the list {} to <@&{}>.\n{} role holders were not on the list and {} list members did not have the role.",
+            list_name,
+            role_id,
+            only_role.len(),
+            only_list.len()
+        );
+        content += match prefer {
+            "role" => "\nThe list now matches the role.",
+            "list" => "\nThe role now matches the list.",
+            _ => "\nBoth were merged.",
+        };
+        if !failed.is_empty() {
+            const SHOWN_FAILURES: usize = 40;
+            content += "\nCould not update the role of these members, check that the bot's role is above the linked role:\n";
+            content += &failed
+                .iter()
+                .take(SHOWN_FAILURES)
+                .map(|id| format!("<@{}>", id))
+                .collect::<Vec<String>>()
+                .join(", ");
+            if failed.len() > SHOWN_FAILURES {
+                content += &format!(" and {} more", failed.len() - SHOWN_FAILURES);
+            }
+        }
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .content(content)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
             .await
             .expect("Failure communicating with discord api");
     }
 
-    async fn handle_get(&self, command: &CommandInteraction, ctx: &Context) {
-        let guild_id: GuildId = command.guild_id.unwrap();
-        let member_id: UserId = command
-            .member
-            .as_ref()
-            .expect("Interaction not triggered by a member")
-            .user
-            .id;
+    async fn handle_list_managers(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        if !Handler::can_manage_messages(command) {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
 
-        let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
-            .get_mut::<DB>()
-            .expect("Could not find database in bot data");
-        let mut content = format!("You are in the following lists:");
-        if let Ok(mut x) = db.clone().lock() {
-            let list_ids = x.get_lists_with_member(guild_id, member_id).unwrap();
-            for list_id in list_ids {
-                //TODO: overflow
-                let list_names = x.get_list_names(list_id);
-                content += format!("\n{}", list_names.join(", ")).as_str();
-                // if content.len() > MESSAGE_CODE_LIMIT - 80 {
-                // }
+        let mut list_name = "";
+        let mut added: Option<UserId> = None;
+        let mut removed: Option<UserId> = None;
+        for option in &command.data.options {
+            match (option.name.as_str(), &option.value) {
+                ("list", CommandDataOptionValue::String(value)) => list_name = value,
+                ("add", CommandDataOptionValue::User(value)) => added = Some(*value),
+                ("remove", CommandDataOptionValue::User(value)) => removed = Some(*value),
+                _ => (),
             }
         }
-
+
+        let mut content = format!("The list {} does not exist", list_name);
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                if let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) {
+                    content = String::new();
+                    if let Some(user_id) = added {
+                        if x.add_list_manager(list_id, user_id) {
+                            content += &format!("<@{}> now manages {}.\n", user_id, list_name);
+                        } else {
+                            content += &format!("<@{}> already manages {}.\n", user_id, list_name);
+                        }
+                    }
+                    if let Some(user_id) = removed {
+                        if x.remove_list_manager(list_id, user_id) {
+                            content +=
+                                &format!("<@{}> no longer manages {}.\n", user_id, list_name);
+                        } else {
+                            content += &format!("<@{}> did not manage {}.\n", user_id, list_name);
+                        }
+                    }
+                    let managers = x.get_list_managers(list_id);
+                    if managers.is_empty() {
+                        content += &format!("The list {} has no managers.", list_name);
+                    } else {
+                        content += &format!(
+                            "Managers of {}: {}",
+                            list_name,
+                            managers
+                                .iter()
+                                .map(|id| format!("<@{}>", id))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        );
+                    }
+                }
+            };
+        }
         Handler::send_text(&content, command, ctx, true).await;
     }
 
-    async fn handle_add(&self, command: &CommandInteraction, ctx: &Context) {
+    async fn handle_category(&self, command: &CommandInteraction, ctx: &Context) {
         let Some(guild_id) = command.guild_id else {
             Handler::send_not_in_guild(command, ctx).await;
             return;
         };
         if !Handler::can_manage_messages(command) {
-            Handler::send_not_allowed(&command, &ctx).await;
+            Handler::send_not_allowed(command, ctx).await;
             return;
         }
-
-        let Some(CommandDataOptionValue::User(member_id)) = command
-            .data
-            .options
-            .iter()
-            .find(|x| x.name == "user")
-            .and_then(|cdo| Some(&cdo.value))
-        else {
-            Handler::send_text("Missing user parameter.", command, ctx, true).await;
-            return;
+        let subcommand = &command.data.options[0];
+        let CommandDataOptionValue::SubCommand(ref options) = subcommand.value else {
+            panic!("Invalid /category definition");
         };
+        let option = |name: &str| {
+            options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+                .unwrap_or("")
+        };
+        let name = option("name");
 
-        let mut content = format!(
-            "Attempting to add user with id {} to {} lists:",
-            member_id,
-            command.data.options.len() - 1
-        );
-
-        for list_name in command.data.options.iter() {
-            if list_name.name == "user" {
-                continue;
+        let mut content = String::new();
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                content = match subcommand.name.as_str() {
+                    "create" => match x
+                        .add_category(guild_id, name)
+                        .expect("Failed to create category")
+                    {
+                        Some(_) => format!("Created the category {}.", name),
+                        None => format!("The category {} already exists.", name),
+                    },
+                    "rename" => match x.get_category_id(guild_id, name) {
+                        Some(category_id) => {
+                            let new_name = option("new_name");
+                            if x.rename_category(category_id, new_name)
+                                .expect("Failed to rename category")
+                            {
+                                format!("Renamed the category {} to {}.", name, new_name)
+                            } else {
+                                format!("The category {} already exists.", new_name)
+                            }
+                        }
+                        None => format!("The category {} does not exist.", name),
+                    },
+                    "delete" => match x.get_category_id(guild_id, name) {
+                        Some(category_id) => {
+                            x.remove_category(category_id)
+                                .expect("Failed to delete category");
+                            format!("Deleted the category {}.", name)
+                        }
+                        None => format!("The category {} does not exist.", name),
+                    },
+                    "assign" => {
+                        let list_name = option("list");
+                        let category = option("category");
+                        match (
+                            x.get_list_id_by_name(list_name, guild_id),
+                            x.get_category_id(guild_id, category),
+                        ) {
+                            (None, _) => format!("The list {} does not exist.", list_name),
+                            (Some(list_id), _) if category.is_empty() => {
+                                x.set_list_category(list_id, None);
+                                format!("The list {} is no longer in a category.", list_name)
+                            }
+                            (Some(_), None) => {
+                                format!("The category {} does not exist.", category)
+                            }
+                            (Some(list_id), Some(category_id)) => {
+                                x.set_list_category(list_id, Some(category_id));
+                                format!("Moved the list {} to {}.", list_name, category)
+                            }
+                        }
+                    }
+                    _ => String::new(),
+                };
             };
-            let list_name = list_name.value.as_str().unwrap();
-            match self
-                .add_member(guild_id, list_name, *member_id, true, ctx)
-                .await
-            {
-                JoinResult::AlreadyMember => {
-                    content += format!("\nUser was already in list {}", list_name).as_str();
-                }
-                JoinResult::Succes => {
-                    content += format!("\nAdded to list {}", list_name).as_str();
-                }
-                JoinResult::ListDoesNotExist => {
-                    content += format!("\nList {} does not exist", list_name).as_str();
-                }
-                JoinResult::MissingPerms => {
-                    content += format!(
-                        "\nYou do not have permission to add user to list {}.",
-                        list_name
-                    )
-                    .as_str();
-                }
-                JoinResult::BotError => {
-                    content +=
-                        format!("\nAn error occured adding user to list {}", list_name).as_str();
-                }
-            }
         }
         Handler::send_text(&content, command, ctx, false).await;
     }
 
-    async fn handle_kick(&self, command: &CommandInteraction, ctx: &Context) {
+    /// Suggests categories, or lists for the list option of /category assign.
+    async fn autocomplete_category(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        const SUGGESTIONS: usize = 5;
+        let mut suggestions: Vec<String> = Vec::new();
+        if let (Some(guild_id), Some(focused)) =
+            (autocomplete.guild_id, autocomplete.data.autocomplete())
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                suggestions = if focused.name == "list" {
+                    x.get_list_aliases_by_search(guild_id, 0, SUGGESTIONS, focused.value, true)
+                } else {
+                    x.get_category_names(guild_id, focused.value)
+                        .into_iter()
+                        .take(SUGGESTIONS)
+                        .collect()
+                };
+            };
+        }
+
+        let mut resp = CreateAutocompleteResponse::new();
+        for suggestion in suggestions {
+            resp = resp.add_string_choice(&suggestion, &suggestion);
+        }
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
+            .await
+            .expect("Failure communicating with discord api");
+    }
+
+    async fn handle_include(&self, command: &CommandInteraction, ctx: &Context) {
         let Some(guild_id) = command.guild_id else {
             Handler::send_not_in_guild(command, ctx).await;
             return;
         };
         if !Handler::can_manage_messages(command) {
-            Handler::send_not_allowed(&command, &ctx).await;
+            Handler::send_not_allowed(command, ctx).await;
             return;
         }
 
-        let member_id = command
-            .data
-            .options
-            .iter()
-            .find(|x| x.name == "user")
-            .unwrap()
-            .value
-            .as_user_id()
-            .expect("Not given a valid user");
-
-        let list_names: Vec<CommandDataOption> = command.data.options.clone();
-
-        let mut content = format!(
-            "Attempting to remove user with id {} from {} lists:",
-            member_id,
-            list_names.len() - 1
-        );
-        for list_name in list_names {
-            if list_name.name == "user" {
-                continue;
-            };
-            let list_name_str = list_name.value.as_str().unwrap();
+        let option = |name: &str| {
+            command
+                .data
+                .options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+        };
+        let list_name = option("list").unwrap_or("");
 
-            match self
-                .remove_member(guild_id, list_name_str, member_id, true, ctx)
-                .await
-            {
-                JoinResult::Succes => {
-                    content += format!("\nRemoved from list {}", list_name_str).as_str();
-                }
-                JoinResult::AlreadyMember => {
-                    content +=
-                        format!("\nThis person was not in the list {}", list_name_str).as_str();
-                }
-                JoinResult::ListDoesNotExist => {
-                    content += format!("\nThe list {} does not exist", list_name_str).as_str();
-                }
-                JoinResult::MissingPerms => {
-                    content += format!(
-                        "\nYou do not have permission to remove users from the list {}",
-                        list_name_str
-                    )
-                    .as_str();
-                }
-                JoinResult::BotError => {
-                    content +=
-                        format!("\nFailed to remove member from list {}", list_name_str).as_str();
+        let mut content = format!("The list {} does not exist.", list_name);
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                if let Some(list_id) = x.get_list_id_by_name(list_name, guild_id) {
+                    content = String::new();
+                    if let Some(added) = option("add") {
+                        match x.get_list_id_by_name(added, guild_id) {
+                            None => content += &format!("The list {} does not exist.\n", added),
+                            Some(child_id) if x.would_create_cycle(list_id, child_id) => {
+                                content += &format!(
+                                    "Cannot include {} in {}, {} would end up including itself.\n",
+                                    added, list_name, list_name
+                                )
+                            }
+                            Some(child_id) => {
+                                if x.add_list_include(list_id, child_id)
+                                    .expect("Failed to include list")
+                                {
+                                    content += &format!("{} now includes {}.\n", list_name, added);
+                                } else {
+                                    content +=
+                                        &format!("{} already includes {}.\n", list_name, added);
+                                }
+                            }
+                        }
+                    }
+                    if let Some(removed) = option("remove") {
+                        let removed_id = x.get_list_id_by_name(removed, guild_id);
+                        if removed_id.is_some_and(|child_id| {
+                            x.remove_list_include(list_id, child_id)
+                                .expect("Failed to remove included list")
+                        }) {
+                            content += &format!("{} no longer includes {}.\n", list_name, removed);
+                        } else {
+                            content += &format!("{} did not include {}.\n", list_name, removed);
+                        }
+                    }
+                    let included = x.get_included_lists(list_id);
+                    if included.is_empty() {
+                        content += &format!("The list {} includes no other lists.", list_name);
+                    } else {
+                        content += &format!(
+                            "The list {} includes: {}",
+                            list_name,
+                            included
+                                .into_iter()
+                                .filter_map(|child_id| x.get_list_names(child_id).first().cloned())
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        );
+                    }
                 }
-            }
+            };
         }
-
         Handler::send_text(&content, command, ctx, false).await;
     }
 
@@ -1042,6 +4038,7 @@ impl Handler {
         guild_id: GuildId,
         page: i64,
         filter: String,
+        category: String,
         ctx: &Context,
     ) -> (CreateEmbed, Option<CreateActionRow>) {
         const PAGESIZE: usize = 20;
@@ -1051,16 +4048,25 @@ impl Handler {
         let mut labels: Vec<String> = Vec::new();
         let mut page_selection: (usize, usize) = (0, 0);
         let page_count: usize;
-        let mut visible_lists: Vec<(String, String)> = Vec::new();
+        let mut visible_lists: Vec<(String, String, Option<String>)> = Vec::new();
+        let mut grouped = false;
+        let mut category_exists = true;
+        let mut category_id = None;
 
         let data = ctx.data.write().await;
         let BotData { database: db, .. } = data.get::<DB>().unwrap();
 
         if let Ok(mut x) = db.clone().lock() {
-            maxlists = x.count_lists_by_search(guild_id, filter.as_str(), false);
+            // Search results are ordered by relevance rather than by category
+            grouped = filter.is_empty() && !x.get_category_names(guild_id, "").is_empty();
+            category_id = x.get_category_id(guild_id, &category);
+            category_exists = category.is_empty() || category_id.is_some();
+            if category_exists {
+                maxlists = x.count_lists_by_search(guild_id, filter.as_str(), category_id, false);
+            }
             if maxlists > 0 {
                 lists = x
-                    .get_lists_by_search(guild_id, filter.as_str(), false)
+                    .get_lists_by_search(guild_id, filter.as_str(), category_id, false)
                     .unwrap();
                 if page >= 0 {
                     let page = page as usize;
@@ -1075,6 +4081,7 @@ impl Handler {
                                 visible_lists.push((
                                     x.get_list_names(lists[list_index].id).join(", "),
                                     lists[list_index].description.clone(),
+                                    lists[list_index].category.clone(),
                                 ));
                             }
                         }
@@ -1084,79 +4091,342 @@ impl Handler {
         }
         let mut embed = CreateEmbed::default();
 
-        if maxlists == 0 {
-            embed = embed.color((255, 0, 0)).title("No lists found.");
-            succes = false;
-        } else if page < 0 || PAGESIZE * (page as usize) >= maxlists {
-            embed = embed.color((255, 127, 28)).title("List page out of range.");
-            succes = true;
+        if !category_exists {
+            embed = embed
+                .color((255, 0, 0))
+                .title(format!("The category {} does not exist.", category));
+            succes = false;
+        } else if maxlists == 0 {
+            embed = embed.color((255, 0, 0)).title("No lists found.");
+            succes = false;
+        } else if page < 0 || PAGESIZE * (page as usize) >= maxlists {
+            embed = embed.color((255, 127, 28)).title("List page out of range.");
+            succes = true;
+        } else {
+            embed = embed.color((127, 255, 160)).title(format!(
+                "Showing lists {}-{} out of {}:",
+                page_selection.0 + 1,
+                page_selection.1,
+                maxlists
+            ));
+            let mut lines: Vec<String> = Vec::new();
+            let mut current_category = None;
+            for (index, (names, description, category)) in visible_lists.iter().enumerate() {
+                // A header whenever a new category starts on this page
+                if grouped && (index == 0 || current_category != Some(category)) {
+                    lines.push(format!(
+                        "**{}**",
+                        category.as_deref().unwrap_or("Uncategorized")
+                    ));
+                }
+                current_category = Some(category);
+                lines.push(match description.as_str() {
+                    "" => format!("- {}", names),
+                    _ => format!("- {}\n    {}", names, description),
+                });
+            }
+            embed = embed.description(lines.join("\n"));
+            succes = true;
+        }
+
+        if !succes {
+            return (embed, None);
+        }
+
+        let mut select_menu_options: Vec<CreateSelectMenuOption> = Vec::new();
+        for (i, label) in labels.iter().enumerate() {
+            select_menu_options.push(CreateSelectMenuOption::new(label, i.to_string()));
+        }
+        // The category id and filter are carried in the custom id as "category|filter", the
+        // filter goes last as it may contain '|' itself
+        let mut select_menu = CreateSelectMenu::new(
+            format!(
+                "{}|{}",
+                category_id.map(|id| id.to_string()).unwrap_or_default(),
+                filter
+            ),
+            serenity::all::CreateSelectMenuKind::String {
+                options: (select_menu_options),
+            },
+        );
+        select_menu = select_menu.placeholder("Navigate between pages");
+        let action_row = CreateActionRow::SelectMenu(select_menu);
+        return (embed, Some(action_row));
+    }
+
+    async fn handle_list(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let mut page: i64 = 0;
+        let mut filter: String = "".to_string();
+        let mut category: String = "".to_string();
+        for option in command.data.options.iter() {
+            if option.name == "page" {
+                page = option.value.as_i64().unwrap() - 1;
+            } else if option.name == "filter" {
+                filter = option.value.as_str().unwrap().to_string();
+            } else if option.name == "category" {
+                category = option.value.as_str().unwrap().to_string();
+            }
+        }
+
+        let (embed, action_row) = self
+            .compose_list(guild_id, page, filter, category, ctx)
+            .await;
+
+        let mut response_message = CreateInteractionResponseMessage::new();
+        response_message = response_message.ephemeral(true).add_embed(embed);
+        // Ensure V does not replace anything?
+        if let Some(action_row) = action_row {
+            response_message = response_message.components(vec![action_row]);
+        }
+
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(response_message),
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn list_page_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+        let mut page = 0;
+        if let ComponentInteractionDataKind::StringSelect { ref values } = component.data.kind {
+            page = values
+                .get(0)
+                .and_then(|f| f.parse::<i64>().ok())
+                .unwrap_or(0);
+        }
+        let guild_id = component.guild_id.unwrap();
+        let (category_id, filter) = component.data.custom_id.split_once('|').unwrap_or_default();
+
+        let mut category = Some(String::new());
+        if let Ok(category_id) = category_id.parse::<u64>() {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            category = db
+                .lock()
+                .ok()
+                .and_then(|x| x.get_category_name(category_id));
+        }
+
+        let embed = match category {
+            Some(category) => {
+                self.compose_list(guild_id, page, filter.to_string(), category, ctx)
+                    .await
+                    .0
+            }
+            None => CreateEmbed::default()
+                .color((255, 0, 0))
+                .title("This category no longer exists."),
+        };
+
+        component.defer(&ctx).await.unwrap();
+        component
+            .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+            .await
+            .unwrap();
+    }
+
+    /// Reads a UTC time written as `YYYY-MM-DD` or `YYYY-MM-DD HH:MM` into a unix timestamp.
+    fn parse_utc_time(text: &str) -> Option<u64> {
+        let text = text.trim();
+        let rfc3339 = if text.len() == 10 {
+            format!("{}T00:00:00Z", text)
         } else {
-            embed = embed.color((127, 255, 160)).title(format!(
-                "Showing lists {}-{} out of {}:",
-                page_selection.0 + 1,
-                page_selection.1,
-                maxlists
-            ));
-            embed = embed.description(
-                visible_lists
-                    .iter()
-                    .map(|x| match x.1.as_str() {
-                        "" => format!("- {}", x.0),
-                        _ => format!("- {}\n    {}", x.0, x.1),
-                    })
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-            );
-            succes = true;
+            format!("{}:00Z", text.replacen(' ', "T", 1))
+        };
+        serenity::model::Timestamp::parse(&rfc3339)
+            .ok()
+            .and_then(|time| u64::try_from(time.unix_timestamp()).ok())
+    }
+
+    async fn compose_ping_history(
+        &self,
+        guild_id: GuildId,
+        page: i64,
+        filter: PingHistoryFilter,
+        ctx: &Context,
+    ) -> (CreateEmbed, Option<CreateActionRow>) {
+        const PAGESIZE: usize = 10;
+        let mut total: usize = 0;
+        let mut lines: Vec<String> = Vec::new();
+
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data.get::<DB>().unwrap();
+
+        if let Ok(mut x) = db.clone().lock() {
+            total = x.count_ping_history(guild_id, &filter);
+            if page >= 0 && (page as usize) * PAGESIZE < total {
+                let records = x
+                    .get_ping_history(guild_id, &filter, page as usize * PAGESIZE, PAGESIZE)
+                    .unwrap();
+                for record in records {
+                    let names = record
+                        .list_ids
+                        .iter()
+                        .map(|list_id| {
+                            x.get_list_names(*list_id)
+                                .into_iter()
+                                .next()
+                                .unwrap_or_else(|| "a removed list".to_string())
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let jump = match record.message_ids.first() {
+                        Some(message_id) => format!(
+                            " ([jump](https://discord.com/channels/{}/{}/{}))",
+                            guild_id, record.channel_id, message_id
+                        ),
+                        None => "".to_string(),
+                    };
+                    lines.push(format!(
+                        "- <t:{}:f> <@{}> pinged {} in <#{}>, {} members{}",
+                        record.timestamp,
+                        record.user_id,
+                        names,
+                        record.channel_id,
+                        record.member_count,
+                        jump
+                    ));
+                }
+            }
         }
 
-        if !succes {
+        let mut embed = CreateEmbed::default();
+        if total == 0 {
+            embed = embed.color((255, 0, 0)).title("No pings found.");
             return (embed, None);
+        } else if lines.is_empty() {
+            embed = embed
+                .color((255, 127, 28))
+                .title("History page out of range.");
+        } else {
+            let page = page as usize;
+            embed = embed
+                .color((127, 255, 160))
+                .title(format!(
+                    "Showing pings {}-{} out of {}:",
+                    page * PAGESIZE + 1,
+                    page * PAGESIZE + lines.len(),
+                    total
+                ))
+                .description(lines.join("\n"));
         }
 
-        let mut select_menu_options: Vec<CreateSelectMenuOption> = Vec::new();
-        for (i, label) in labels.iter().enumerate() {
-            select_menu_options.push(CreateSelectMenuOption::new(label, i.to_string()));
-        }
-        let mut select_menu = CreateSelectMenu::new(
-            if filter != "" {
-                filter
-            } else {
-                "|".to_string()
-            },
+        // Discord allows 25 options, so only offer the pages around the current one.
+        let page_count = 1 + (total - 1) / PAGESIZE;
+        let first_page = min(page.max(0) as usize, page_count.saturating_sub(1)).saturating_sub(12);
+        let select_menu_options = (first_page..min(page_count, first_page + 25))
+            .map(|page_index| {
+                CreateSelectMenuOption::new(
+                    format!(
+                        "{}:{}",
+                        page_index * PAGESIZE + 1,
+                        min(total, (page_index + 1) * PAGESIZE)
+                    ),
+                    page_index.to_string(),
+                )
+            })
+            .collect::<Vec<CreateSelectMenuOption>>();
+        let select_menu = CreateSelectMenu::new(
+            format!(
+                "{}|{}|{}|{}",
+                filter.list_id.unwrap_or(0),
+                filter.user_id.map_or(0, UserId::get),
+                filter.since,
+                filter.until
+            ),
             serenity::all::CreateSelectMenuKind::String {
-                options: (select_menu_options),
+                options: select_menu_options,
             },
-        );
-        select_menu = select_menu.placeholder("Navigate between pages");
-        let action_row = CreateActionRow::SelectMenu(select_menu);
-        return (embed, Some(action_row));
+        )
+        .placeholder("Navigate between pages");
+        (embed, Some(CreateActionRow::SelectMenu(select_menu)))
     }
 
-    async fn handle_list(&self, command: &CommandInteraction, ctx: &Context) {
+    async fn handle_ping_history(&self, command: &CommandInteraction, ctx: &Context) {
         let Some(guild_id) = command.guild_id else {
             Handler::send_not_in_guild(command, ctx).await;
             return;
         };
+        if !Handler::can_manage_messages(command) {
+            Handler::send_not_allowed(command, ctx).await;
+            return;
+        }
+
         let mut page: i64 = 0;
-        let mut filter: String = "".to_string();
+        let mut filter = PingHistoryFilter {
+            list_id: None,
+            user_id: None,
+            since: 0,
+            until: u64::MAX >> 1,
+        };
         for option in command.data.options.iter() {
-            if option.name == "page" {
-                page = option.value.as_i64().unwrap() - 1;
-            } else if option.name == "filter" {
-                filter = option.value.as_str().unwrap().to_string();
+            match (option.name.as_str(), &option.value) {
+                ("page", CommandDataOptionValue::Integer(value)) => page = value - 1,
+                ("user", CommandDataOptionValue::User(user_id)) => filter.user_id = Some(*user_id),
+                ("list", CommandDataOptionValue::String(list_name)) => {
+                    let mut list_id = None;
+                    {
+                        let data = ctx.data.read().await;
+                        let BotData { database: db, .. } = data.get::<DB>().unwrap();
+                        if let Ok(mut x) = db.clone().lock() {
+                            list_id = x.get_list_id_by_name(list_name, guild_id);
+                        }
+                    }
+                    let Some(list_id) = list_id else {
+                        Handler::send_text(
+                            &format!("The list {} does not exist.", list_name),
+                            command,
+                            ctx,
+                            true,
+                        )
+                        .await;
+                        return;
+                    };
+                    filter.list_id = Some(list_id);
+                }
+                (name @ ("from" | "to"), CommandDataOptionValue::String(time)) => {
+                    let Some(timestamp) = Handler::parse_utc_time(time) else {
+                        Handler::send_text(
+                            &format!(
+                                "Could not read \"{}\", use YYYY-MM-DD or YYYY-MM-DD HH:MM in UTC.",
+                                time
+                            ),
+                            command,
+                            ctx,
+                            true,
+                        )
+                        .await;
+                        return;
+                    };
+                    if name == "from" {
+                        filter.since = timestamp;
+                    } else if time.trim().len() == 10 {
+                        // A bare date includes the whole day.
+                        filter.until = timestamp + 86400;
+                    } else {
+                        filter.until = timestamp;
+                    }
+                }
+                _ => (),
             }
         }
 
-        let (embed, action_row) = self.compose_list(guild_id, page, filter, ctx).await;
+        let (embed, action_row) = self.compose_ping_history(guild_id, page, filter, ctx).await;
 
-        let mut response_message = CreateInteractionResponseMessage::new();
-        response_message = response_message.ephemeral(true).add_embed(embed);
-        // Ensure V does not replace anything?
+        let mut response_message = CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .add_embed(embed);
         if let Some(action_row) = action_row {
             response_message = response_message.components(vec![action_row]);
         }
-
         command
             .create_response(
                 &ctx.http,
@@ -1166,26 +4436,45 @@ impl Handler {
             .unwrap();
     }
 
-    async fn list_page_from_component(&self, component: &ComponentInteraction, ctx: &Context) {
+    async fn ping_history_page_from_component(
+        &self,
+        component: &ComponentInteraction,
+        ctx: &Context,
+    ) {
         let mut page = 0;
         if let ComponentInteractionDataKind::StringSelect { ref values } = component.data.kind {
             page = values
-                .get(0)
+                .first()
                 .and_then(|f| f.parse::<i64>().ok())
                 .unwrap_or(0);
         }
         let guild_id = component.guild_id.unwrap();
-        let filter = if component.data.custom_id == "|" {
-            "".to_string()
-        } else {
-            component.data.custom_id.clone()
+        let fields = component
+            .data
+            .custom_id
+            .split('|')
+            .map(|field| field.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<u64>>();
+        let [list_id, user_id, since, until] = fields[..] else {
+            return;
+        };
+        let filter = PingHistoryFilter {
+            list_id: (list_id != 0).then_some(list_id),
+            user_id: (user_id != 0).then(|| UserId::new(user_id)),
+            since,
+            until,
         };
 
-        let (embed, _action_row) = self.compose_list(guild_id, page, filter, ctx).await;
+        let (embed, action_row) = self.compose_ping_history(guild_id, page, filter, ctx).await;
 
         component.defer(&ctx).await.unwrap();
         component
-            .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .embed(embed)
+                    .components(action_row.into_iter().collect()),
+            )
             .await
             .unwrap();
     }
@@ -1195,15 +4484,31 @@ impl Handler {
             Handler::send_not_in_guild(command, ctx).await;
             return;
         };
-        if !Handler::can_manage_messages(command) {
-            Handler::send_not_allowed(&command, &ctx).await;
-            return;
-        }
 
         let mut embed = CreateEmbed::default();
 
         let subcom = &command.data.options[0];
 
+        // List managers may only touch the description and cooldown of their own lists
+        let as_admin = Handler::can_manage_messages(command);
+        if !as_admin {
+            let managed_list = match &subcom.value {
+                CommandDataOptionValue::SubCommand(options) if subcom.name == "list" => options
+                    .iter()
+                    .find(|x| x.name.as_str() == "list")
+                    .and_then(|x| x.value.as_str()),
+                _ => None,
+            };
+            let allowed = match managed_list {
+                Some(list_name) => Handler::can_manage_list(command, ctx, list_name).await,
+                None => false,
+            };
+            if !allowed {
+                Handler::send_not_allowed(command, ctx).await;
+                return;
+            }
+        }
+
         let data = ctx.data.write().await;
         let BotData { database: db, .. } = data.get::<DB>().expect("Cannot find database");
         if let Ok(mut x) = db.clone().lock() {
@@ -1211,14 +4516,22 @@ impl Handler {
                 CommandDataOption { ref name, .. } if name == "show" => {
                     let (a, b, c) = x.get_guild_ping_data(guild_id);
                     let (d, e, f) = x.get_propose_settings(guild_id);
+                    let g = x.get_departure_grace(guild_id);
+                    let (h, i) = x.get_guild_quota(guild_id);
+                    let j = x.get_confirm_threshold(guild_id);
+                    let k = x
+                        .get_approval_channel(guild_id)
+                        .map_or("list managers".to_string(), |c| format!("<#{}>", c));
+                    let l = x.get_expiry_notice(guild_id);
+                    let m = x.get_archive_retention(guild_id);
                     embed = embed
                         .color((0, 0, 0))
                         .description("test")
                         .field(
                             "Guild-wide",
                             format!(
-                                "allow pings {}\nshared cooldown {}\nlist cooldown {}",
-                                b, a, c
+                                "allow pings {}\nshared cooldown {}\nlist cooldown {}\ndeparture grace period {}\nping quota {} per {} seconds\nconfirm pings above {} members\njoin requests go to {}\nnotify expired memberships {}\nkeep removed lists {} seconds",
+                                b, a, c, g, h, i, j, k, l, m
                             ),
                             false,
                         )
@@ -1275,6 +4588,110 @@ impl Handler {
                                     panic!("The parameter disable_propose for configure role is incorrectly configured");
                                 }
                             }
+                            "departure_grace_period" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    x.set_departure_grace(guild_id, b as u64).unwrap();
+                                    embed = embed.field(
+                                        "Departure grace period",
+                                        format!("Members who left are removed from lists after {} seconds", b),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter departure_grace_period for configure guild is incorrectly configured");
+                                }
+                            }
+                            "ping_quota" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    x.set_guild_quota(guild_id, b as u64).unwrap();
+                                    embed = embed.field(
+                                        "Ping quota",
+                                        format!("Members can send {} pings per quota window, 0 is unlimited", b),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter ping_quota for configure guild is incorrectly configured");
+                                }
+                            }
+                            "confirm_threshold" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    x.set_confirm_threshold(guild_id, b as u64).unwrap();
+                                    embed = embed.field(
+                                        "Confirmation threshold",
+                                        format!("Pings to more than {} members need to be confirmed, 0 disables this", b),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter confirm_threshold for configure guild is incorrectly configured");
+                                }
+                            }
+                            "approval_channel" => {
+                                if let CommandDataOptionValue::Channel(channel) = setting.value {
+                                    x.set_approval_channel(guild_id, Some(channel)).unwrap();
+                                    embed = embed.field(
+                                        "Approval channel",
+                                        format!("Join requests are posted in <#{}>", channel),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter approval_channel for configure guild is incorrectly configured");
+                                }
+                            }
+                            "expiry_notice" => {
+                                if let CommandDataOptionValue::Boolean(b) = setting.value {
+                                    x.set_expiry_notice(guild_id, b).unwrap();
+                                    embed = embed.field(
+                                        "Expiry notice",
+                                        format!(
+                                            "Members are told when a temporary membership ends: {}",
+                                            b
+                                        ),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter expiry_notice for configure guild is incorrectly configured");
+                                }
+                            }
+                            "archive_retention" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    x.set_archive_retention(guild_id, b as u64).unwrap();
+                                    embed = embed.field(
+                                        "Archive retention",
+                                        format!(
+                                            "Removed lists are deleted for good after {} seconds",
+                                            b
+                                        ),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter archive_retention for configure guild is incorrectly configured");
+                                }
+                            }
+                            "approval_by_dm" => {
+                                if let CommandDataOptionValue::Boolean(b) = setting.value {
+                                    if b {
+                                        x.set_approval_channel(guild_id, None).unwrap();
+                                        embed = embed.field(
+                                            "Approval channel",
+                                            "Join requests are sent to the list managers",
+                                            false,
+                                        );
+                                    }
+                                } else {
+                                    panic!("The parameter approval_by_dm for configure guild is incorrectly configured");
+                                }
+                            }
+                            "quota_window" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    x.set_guild_quota_window(guild_id, b as u64).unwrap();
+                                    embed = embed.field(
+                                        "Quota window",
+                                        format!("Pings count towards the quota for {} seconds", b),
+                                        false,
+                                    );
+                                } else {
+                                    panic!("The parameter quota_window for configure guild is incorrectly configured");
+                                }
+                            }
                             _ => (),
                         }
                     }
@@ -1331,6 +4748,15 @@ impl Handler {
                                     panic!("The parameter exclude_from_cooldown for configure role is incorrectly configured");
                                 }
                             }
+                            "ping_quota" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    let b = b.max(-1);
+                                    x.set_role_quota(guild_id, role, b).unwrap();
+                                    embed = embed.field("role ping quota", format!("{}", b), false);
+                                } else {
+                                    panic!("The parameter ping_quota for configure role is incorrectly configured");
+                                }
+                            }
                             _ => (),
                         }
                     }
@@ -1387,6 +4813,23 @@ impl Handler {
                                     panic!("The parameter exclude_from_cooldown for configure role is incorrectly configured");
                                 }
                             }
+                            "ping_quota" => {
+                                if let CommandDataOptionValue::Integer(b) = setting.value {
+                                    let b = b.max(-1);
+                                    if x.set_user_quota(guild_id, user, b) {
+                                        embed =
+                                            embed.field("user ping quota", format!("{}", b), false);
+                                    } else {
+                                        embed = embed.field(
+                                            "user ping quota",
+                                            "The ping quota could not be saved",
+                                            false,
+                                        );
+                                    }
+                                } else {
+                                    panic!("The parameter ping_quota for configure user is incorrectly configured");
+                                }
+                            }
                             _ => (),
                         }
                     }
@@ -1405,6 +4848,19 @@ impl Handler {
                     };
                     if let Some(list) = x.get_list_id_by_name(list_str, guild_id) {
                         for setting in options {
+                            if !as_admin
+                                && !matches!(
+                                    setting.name.as_str(),
+                                    "list" | "description" | "cooldown"
+                                )
+                            {
+                                embed = embed.field(
+                                    "Not allowed",
+                                    format!("Only moderators can change {}", setting.name),
+                                    false,
+                                );
+                                continue;
+                            }
                             match setting.name.as_str() {
                                 "description" => {
                                     if let CommandDataOptionValue::String(ref description) =
@@ -1424,12 +4880,21 @@ impl Handler {
                                     if let CommandDataOptionValue::Integer(ref cooldown) =
                                         setting.value
                                     {
-                                        x.set_cooldown(list, *cooldown);
-                                        embed = embed.field(
-                                            "set cooldown",
-                                            format!("{}", cooldown),
-                                            false,
-                                        );
+                                        if *cooldown < -1
+                                            || x.set_cooldown(list, *cooldown).is_err()
+                                        {
+                                            embed = embed.field(
+                                                "Invalid cooldown",
+                                                format!("The cooldown {} is not valid", cooldown),
+                                                false,
+                                            );
+                                        } else {
+                                            embed = embed.field(
+                                                "set cooldown",
+                                                format!("{}", cooldown),
+                                                false,
+                                            );
+                                        }
                                     } else {
                                         panic!("The parameter cooldown for configure list is incorrectly configured");
                                     }
@@ -1464,6 +4929,30 @@ impl Handler {
                                         panic!("The parameter allow_ping for configure list is incorrectly configured");
                                     }
                                 }
+                                "delivery" => {
+                                    if let CommandDataOptionValue::String(ref delivery) =
+                                        setting.value
+                                    {
+                                        let mode = DeliveryMode::from_str(delivery).unwrap();
+                                        x.set_delivery_mode(list, mode);
+                                        embed =
+                                            embed.field("set delivery", format!("{}", mode), false);
+                                    } else {
+                                        panic!("The parameter delivery for configure list is incorrectly configured");
+                                    }
+                                }
+                                "require_approval" => {
+                                    if let CommandDataOptionValue::Boolean(b) = setting.value {
+                                        x.set_requires_approval(list, b);
+                                        embed = embed.field(
+                                            "require approval",
+                                            format!("{}", b),
+                                            false,
+                                        );
+                                    } else {
+                                        panic!("The parameter require_approval for configure list is incorrectly configured");
+                                    }
+                                }
                                 "show" => {
                                     if let CommandDataOptionValue::Boolean(b) = setting.value {
                                         x.set_visible(list, b);
@@ -1483,6 +4972,92 @@ impl Handler {
                         );
                     }
                 }
+                CommandDataOption {
+                    ref name,
+                    value: CommandDataOptionValue::SubCommand(options),
+                    ..
+                } if name == "category" => {
+                    let category_value = options
+                        .iter()
+                        .find(|x| x.name.as_str() == "category")
+                        .expect("No category argument given");
+                    let CommandDataOptionValue::String(ref category_str) = category_value.value
+                    else {
+                        panic!("Category argument is not valid")
+                    };
+                    if let Some(category) = x.get_category_id(guild_id, category_str) {
+                        let lists = x.get_lists_in_category(category);
+                        embed = embed.description(format!(
+                            "Configuring {} lists in {}",
+                            lists.len(),
+                            category_str
+                        ));
+                        for setting in options {
+                            match setting.name.as_str() {
+                                "cooldown" => {
+                                    if let CommandDataOptionValue::Integer(ref cooldown) =
+                                        setting.value
+                                    {
+                                        if *cooldown < -1
+                                            || lists.iter().any(|list| {
+                                                x.set_cooldown(*list, *cooldown).is_err()
+                                            })
+                                        {
+                                            embed = embed.field(
+                                                "Invalid cooldown",
+                                                format!("The cooldown {} is not valid", cooldown),
+                                                false,
+                                            );
+                                        } else {
+                                            embed = embed.field(
+                                                "set cooldown",
+                                                format!("{}", cooldown),
+                                                false,
+                                            );
+                                        }
+                                    } else {
+                                        panic!("The parameter cooldown for configure category is incorrectly configured");
+                                    }
+                                }
+                                "allow_join" => {
+                                    if let CommandDataOptionValue::String(ref joinable) =
+                                        setting.value
+                                    {
+                                        let perm = PERMISSION::from_str(joinable).unwrap();
+                                        for list in &lists {
+                                            x.set_joinable(*list, perm);
+                                        }
+                                        embed =
+                                            embed.field("set joinable", format!("{}", perm), false);
+                                    } else {
+                                        panic!("The parameter allow_join for configure category is incorrectly configured");
+                                    }
+                                }
+                                "allow_ping" => {
+                                    if let CommandDataOptionValue::String(ref pingable) =
+                                        setting.value
+                                    {
+                                        let perm = PERMISSION::from_str(pingable).unwrap();
+                                        for list in &lists {
+                                            x.set_pingable(*list, perm);
+                                        }
+                                        embed =
+                                            embed.field("allow ping", format!("{}", perm), false);
+                                    } else {
+                                        panic!("The parameter allow_ping for configure category is incorrectly configured");
+                                    }
+                                }
+                                _ => (),
+                            }
+                        }
+                    } else {
+                        embed = embed.field(
+                            "Category not found",
+                            format!("The category with name {} was not found.", category_str),
+                            false,
+                        );
+                    }
+                }
                 CommandDataOption {
                     ref name,
                     value: CommandDataOptionValue::SubCommand(options),
@@ -1627,6 +5202,13 @@ impl Handler {
     }
 
     async fn autocomplete_configure(&self, autocomplete: &CommandInteraction, ctx: &Context) {
+        if autocomplete
+            .data
+            .autocomplete()
+            .is_some_and(|focused| focused.name == "category")
+        {
+            return self.autocomplete_category(autocomplete, ctx).await;
+        }
         let Some(guild_id) = autocomplete.guild_id else {
             autocomplete
                 .create_response(
@@ -1637,7 +5219,6 @@ impl Handler {
                 .expect("Failure communicating with discord api");
             return;
         };
-        const SUGGESTIONS: usize = 5;
         let member = autocomplete
             .member
             .as_ref()
@@ -1663,22 +5244,32 @@ impl Handler {
             }
         }
 
-        let mut aliases: Vec<String> = Vec::new();
+        let mut suggestions = Vec::new();
 
         let mut data = ctx.data.write().await;
-        let BotData { database: db, .. } = data
+        let BotData {
+            database: db,
+            alias_indexes,
+            ..
+        } = data
             .get_mut::<DB>()
             .expect("Could not find database in bot data");
 
-        if let Ok(mut x) = db.clone().lock() {
-            aliases = x.get_list_aliases_by_search(guild_id, 0, SUGGESTIONS, filter, member_admin)
-        }
-
-        let mut resp = CreateAutocompleteResponse::new();
-        for list in aliases {
-            resp = resp.add_string_choice(&list, &list);
+        if let Ok(mut x) = db.lock() {
+            // Members that are not moderators only get the lists they manage
+            let managed: Vec<String> = if member_admin {
+                vec![]
+            } else {
+                x.get_managed_list_names(guild_id, member.user.id)
+            };
+            suggestions =
+                Handler::ranked_suggestions(&mut x, alias_indexes, guild_id, filter, |alias| {
+                    member_admin || managed.contains(&alias.name)
+                });
         }
 
+        let resp = Handler::list_choices(suggestions);
+
         autocomplete
             .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp))
             .await
@@ -1723,6 +5314,7 @@ impl Handler {
             .expect("Could not find database in bot data");
 
         let mut override_canpropose: PERMISSION;
+        let name_taken: Option<String>;
 
         if let Ok(mut x) = db.clone().lock() {
             let (general_propose, ..) = x.get_propose_settings(guild_id);
@@ -1743,7 +5335,8 @@ impl Handler {
                 override_canpropose = override_canpropose.combine(role_can_propose);
             }
 
-            if override_canpropose != PERMISSION::DENY {
+            name_taken = Handler::describe_name_taken(&mut x, guild_id, &name, None);
+            if override_canpropose != PERMISSION::DENY && name_taken.is_none() {
                 let timestamp = serenity::model::Timestamp::now().unix_timestamp();
                 proposal_id = x.start_proposal(guild_id, &name, timestamp, channel_id);
                 if let Some(pid) = proposal_id {
@@ -1792,7 +5385,9 @@ impl Handler {
             } // Log here
             return;
         } else {
-            embed = embed.title("This list already exists").color((0, 255, 0));
+            embed = embed
+                .title(name_taken.unwrap_or("This list already exists".to_string()))
+                .color((0, 255, 0));
         }
         command
             .create_response(
@@ -1914,106 +5509,788 @@ impl Handler {
             }
         }
 
-        if let Some((list_id, message_id, channel_id)) = proposal_data {
-            let mut data = ctx.data.write().await;
+        if let Some((list_id, message_id, channel_id)) = proposal_data {
+            let mut data = ctx.data.write().await;
+            let BotData { database: db, .. } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+
+            let mut embed = CreateEmbed::default();
+            if let Ok(mut x) = db.clone().lock() {
+                if x.accept_proposal(list_id) {
+                    embed = embed.title("Proposal acccepted");
+                } else {
+                    if x.get_list_exists(list_id) {
+                        embed = embed.title("Proposal already accepted");
+                    } else {
+                        embed = embed.title("Proposal already removed");
+                    }
+                }
+            }
+            channel_id
+                .edit_message(
+                    &ctx.http,
+                    message_id,
+                    EditMessage::new().embed(embed).components(vec![]),
+                )
+                .await
+                .unwrap();
+        }
+        Handler::send_text(
+            "Check the original proposal message for results.",
+            command,
+            ctx,
+            true,
+        )
+        .await;
+    }
+
+    /// Gets called without guild context automatically
+    async fn external_check_proposals(ctx: &Context) {
+        let mut data = ctx.data.write().await;
+        let BotData { database: db, .. } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+
+        let mut replies: Vec<(ChannelId, MessageId, bool)> = vec![];
+        if let Ok(mut x) = db.clone().lock() {
+            for (guild_id, proposal) in x.get_bot_proposals() {
+                let ProposalStatus::ACTIVE(list_id, votes, timestamp, channel_id, message_id) =
+                    proposal
+                else {
+                    continue;
+                };
+                let (_, vote_timeout, vote_threshold) = x.get_propose_settings(guild_id);
+                if votes >= vote_threshold {
+                    x.accept_proposal(list_id);
+                    if channel_id != 0 && message_id != 0 {
+                        replies.push((channel_id, message_id, true));
+                    }
+                } else if timestamp + vote_timeout <= now {
+                    // Proposals that were voted down are not archived, so they cannot be
+                    // restored and their names are free to be proposed again
+                    x.remove_list(list_id).unwrap();
+                    if channel_id != 0 && message_id != 0 {
+                        replies.push((channel_id, message_id, false));
+                    }
+                }
+                // We'd rather wait a little longer than reach a rate limit.
+                if replies.len() >= 10 {
+                    break;
+                }
+            }
+        }
+
+        let mut awaits = vec![];
+        let mut more_awaits = vec![];
+        for (channel_id, message_id, accepted) in replies {
+            let reference =
+                serenity::model::prelude::MessageReference::from((channel_id, message_id));
+            let new_message = if accepted {
+                "Proposal accepted"
+            } else {
+                "Proposal timed out"
+            };
+            awaits.push(Handler::send_channel(
+                new_message,
+                channel_id,
+                ctx,
+                false,
+                Some(reference),
+            ));
+            let mut embed = CreateEmbed::default();
+
+            embed = embed.description(new_message);
+            more_awaits.push(channel_id.edit_message(
+                &ctx.http,
+                message_id,
+                EditMessage::new().embed(embed),
+            ));
+        }
+        join_all(awaits).await;
+        join_all(more_awaits).await;
+    }
+
+    /// How often a scheduled ping repeats, for listings.
+    fn describe_repeat(ping: &ScheduledPing) -> String {
+        if let Some(schedule) = &ping.schedule {
+            return format!("on the schedule `{}`", schedule);
+        }
+        match ping.interval {
+            0 => "once".to_string(),
+            86400 => "daily".to_string(),
+            604800 => "weekly".to_string(),
+            _ => format!("every {} hours", ping.interval / 3600),
+        }
+    }
+
+    async fn handle_dm_pings(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let enabled = command
+            .data
+            .options
+            .first()
+            .and_then(|option| option.value.as_bool())
+            .expect("Invalid /dm_pings definition");
+
+        let updated = {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.clone().lock() {
+                x.set_user_dm_pings(guild_id, command.user.id, enabled)
+            } else {
+                false
+            }
+        };
+        let content = if !updated {
+            "Your direct message setting could not be saved."
+        } else if enabled {
+            "Lists may ping you by direct message again."
+        } else {
+            "Lists will mention you in their channel instead of sending you a direct message."
+        };
+        Handler::send_text(content, command, ctx, true).await;
+    }
+
+    async fn handle_schedule_ping(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let mut lists = String::new();
+        let mut time = String::new();
+        let mut interval: u64 = 0;
+        let mut channel_id = command.channel_id;
+        let mut note = String::new();
+        let mut schedule: Option<String> = None;
+        for option in &command.data.options {
+            match (option.name.as_str(), &option.value) {
+                ("lists", CommandDataOptionValue::String(value)) => lists = value.clone(),
+                ("time", CommandDataOptionValue::String(value)) => time = value.clone(),
+                ("repeat", CommandDataOptionValue::Integer(value)) => interval = *value as u64,
+                ("schedule", CommandDataOptionValue::String(value)) => {
+                    schedule = Some(value.split_whitespace().collect::<Vec<&str>>().join(" "))
+                }
+                ("channel", CommandDataOptionValue::Channel(value)) => channel_id = *value,
+                ("note", CommandDataOptionValue::String(value)) => note = value.clone(),
+                _ => (),
+            }
+        }
+
+        // Discord resolves the permissions of the invoker in the channel option
+        let permissions = if channel_id == command.channel_id {
+            command
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)
+        } else {
+            command
+                .data
+                .resolved
+                .channels
+                .get(&channel_id)
+                .and_then(|channel| channel.permissions)
+        };
+        if !permissions
+            .is_some_and(|permissions| permissions.view_channel() && permissions.send_messages())
+        {
+            Handler::send_text(
+                "You can only schedule pings in channels you can see and send messages in.",
+                command,
+                ctx,
+                true,
+            )
+            .await;
+            return;
+        }
+
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        let next_time = match (Handler::parse_utc_time(&time), &schedule) {
+            (None, _) if !time.is_empty() => Err(format!(
+                "Could not read \"{}\", use YYYY-MM-DD HH:MM in UTC.",
+                time
+            )),
+            (Some(start), _) if start <= now => Err("That time has already passed.".to_string()),
+            (_, Some(_)) if interval != 0 => {
+                Err("Use either repeat or schedule, not both.".to_string())
+            }
+            (Some(start), None) => Ok(start),
+            (None, None) => Err("Give a time to ping at, or a schedule to repeat on.".to_string()),
+            // A schedule starts at the given time, or right away
+            (start, Some(schedule)) => Recurrence::parse(schedule)
+                .map_err(|reason| {
+                    format!(
+                        "Could not understand the schedule \"{}\": {}.",
+                        schedule, reason
+                    )
+                })
+                .and_then(|recurrence| {
+                    recurrence
+                        .next_after(start.map_or(now, |start| start - 1))
+                        .ok_or_else(|| format!("The schedule \"{}\" never fires.", schedule))
+                }),
+        };
+        let next_time = match next_time {
+            Ok(next_time) => next_time,
+            Err(reason) => {
+                Handler::send_text(&reason, command, ctx, true).await;
+                return;
+            }
+        };
+
+        let mut content = String::new();
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        if let Ok(mut x) = db.clone().lock() {
+            // Permissions and cooldowns are checked when the ping fires, only the lists are checked now.
+            let names = if x.get_list_id_by_name(&lists, guild_id).is_some() {
+                Ok(vec![lists.clone()])
+            } else {
+                PingQuery::parse(&lists).map(|query| {
+                    query
+                        .list_names()
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect::<Vec<String>>()
+                })
+            };
+            match names {
+                Err(reason) => {
+                    content = format!("Could not understand \"{}\": {}.", lists, reason);
+                }
+                Ok(names) => {
+                    let missing = names
+                        .iter()
+                        .filter(|name| x.get_list_id_by_name(name, guild_id).is_none())
+                        .cloned()
+                        .collect::<Vec<String>>();
+                    if !missing.is_empty() {
+                        content = format!("These lists do not exist: {}", missing.join(", "));
+                    } else {
+                        let ping = ScheduledPing {
+                            id: 0,
+                            guild_id,
+                            channel_id,
+                            user_id: command.user.id,
+                            queries: vec![lists.clone()],
+                            note,
+                            next_time,
+                            interval,
+                            schedule,
+                            queued: false,
+                        };
+                        let id = x
+                            .add_scheduled_ping(&ping)
+                            .expect("Failed to store scheduled ping");
+                        content = format!(
+                            "Scheduled ping #{} of {} in <#{}> at <t:{}:f>, repeating {}.",
+                            id,
+                            lists,
+                            channel_id,
+                            next_time,
+                            Handler::describe_repeat(&ping)
+                        );
+                    }
+                }
+            }
+        }
+        Handler::send_text(&content, command, ctx, true).await;
+    }
+
+    async fn handle_list_scheduled(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let mut scheduled = vec![];
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.clone().lock() {
+                scheduled = x
+                    .get_scheduled_pings(guild_id)
+                    .expect("Failed to read scheduled pings");
+            }
+        }
+
+        let mut embed = CreateEmbed::default();
+        if scheduled.is_empty() {
+            embed = embed.color((255, 0, 0)).title("No pings are scheduled.");
+        } else {
+            let mut description = String::new();
+            for ping in &scheduled {
+                let mut line = format!(
+                    "- #{} <t:{}:f> {} in <#{}> by <@{}>, {}",
+                    ping.id,
+                    ping.next_time,
+                    ping.queries.join(", "),
+                    ping.channel_id,
+                    ping.user_id,
+                    if ping.queued {
+                        "queued until the cooldown ends".to_string()
+                    } else {
+                        format!("repeating {}", Handler::describe_repeat(ping))
+                    }
+                );
+                if !ping.note.is_empty() {
+                    line += format!("\n    {}", ping.note.lines().next().unwrap_or("")).as_str();
+                }
+                if description.len() + line.len() > 4000 {
+                    description += "\n...";
+                    break;
+                }
+                description += line.as_str();
+                description += "\n";
+            }
+            embed = embed
+                .color((127, 255, 160))
+                .title(format!("{} scheduled pings:", scheduled.len()))
+                .description(description);
+        }
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .add_embed(embed),
+                ),
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn handle_cancel_scheduled(&self, command: &CommandInteraction, ctx: &Context) {
+        let Some(guild_id) = command.guild_id else {
+            Handler::send_not_in_guild(command, ctx).await;
+            return;
+        };
+        let id = command
+            .data
+            .options
+            .first()
+            .and_then(|option| option.value.as_i64())
+            .expect("Invalid /cancel_scheduled definition") as u64;
+
+        let mut content = format!("There is no scheduled ping #{}.", id);
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.clone().lock() {
+                match x
+                    .get_scheduled_ping(id)
+                    .expect("Failed to read scheduled pings")
+                {
+                    Some(ping) if ping.guild_id == guild_id => {
+                        if ping.user_id != command.user.id && !Handler::can_manage_messages(command)
+                        {
+                            content =
+                                "Only the user that scheduled this ping can cancel it.".to_string();
+                        } else {
+                            x.remove_scheduled_ping(id)
+                                .expect("Failed to remove scheduled ping");
+                            content = format!("Cancelled scheduled ping #{}.", id);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Handler::send_text(&content, command, ctx, true).await;
+    }
+
+    /// The permissions of the author of a scheduled ping in its channel, if that is still cached.
+    fn scheduled_permissions(
+        ctx: &Context,
+        scheduled: &ScheduledPing,
+        member: &Member,
+    ) -> Option<Permissions> {
+        let guild = ctx.cache.guild(scheduled.guild_id)?;
+        let channel = guild.channels.get(&scheduled.channel_id)?;
+        Some(guild.user_permissions_in(channel, member))
+    }
+
+    /// Sends all scheduled pings that are due, as if their author used /ping at that moment.
+    async fn external_run_scheduled_pings(ctx: &Context) {
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        let mut due = vec![];
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.clone().lock() {
+                due = x
+                    .get_due_scheduled_pings(now)
+                    .expect("Failed to read scheduled pings");
+            }
+        }
+
+        for scheduled in due {
+            let mut notice: Option<String> = None;
+            let mut cancel = false;
+            match scheduled
+                .guild_id
+                .member(&ctx.http, scheduled.user_id)
+                .await
+            {
+                Err(serenity::Error::Http(serenity::http::HttpError::UnsuccessfulRequest(
+                    ref response,
+                ))) if response.status_code.as_u16() == 404 => {
+                    notice = Some(format!(
+                        "Scheduled ping #{} of {} was cancelled because <@{}> is no longer in this server.",
+                        scheduled.id,
+                        scheduled.queries.join(", "),
+                        scheduled.user_id
+                    ));
+                    cancel = true;
+                }
+                // Discord could not be reached, try again on the next round.
+                Err(_) => continue,
+                // The author may have lost access to the channel since scheduling.
+                Ok(member)
+                    if !Handler::scheduled_permissions(ctx, &scheduled, &member).is_some_and(
+                        |permissions| permissions.view_channel() && permissions.send_messages(),
+                    ) =>
+                {
+                    notice = Some(format!(
+                        "Scheduled ping #{} was cancelled because <@{}> can no longer send messages here.",
+                        scheduled.id, scheduled.user_id
+                    ));
+                    cancel = true;
+                }
+                Ok(member) => {
+                    let as_admin = Handler::scheduled_permissions(ctx, &scheduled, &member)
+                        .is_some_and(|permissions| permissions.manage_messages());
+                    let request = PingRequest {
+                        guild_id: scheduled.guild_id,
+                        channel_id: scheduled.channel_id,
+                        user_id: scheduled.user_id,
+                        roles: member.roles.clone(),
+                        as_admin,
+                        queries: scheduled.queries.clone(),
+                        message: (!scheduled.note.is_empty()).then(|| scheduled.note.clone()),
+                    };
+                    match Handler::send_ping_to_channel(ctx, &request, None).await {
+                        Ok(()) => (),
+                        // Cooldowns run out by themselves, try again on the next round.
+                        Err(invalid_lists)
+                            if invalid_lists.iter().all(|(_, reason)| {
+                                matches!(
+                                    reason,
+                                    ListInvalidReasons::OnGlobalCooldown(_)
+                                        | ListInvalidReasons::OnLocalCooldown(_)
+                                        | ListInvalidReasons::MembersUnavailable
+                                )
+                            }) =>
+                        {
+                            continue
+                        }
+                        Err(invalid_lists) => {
+                            notice = Some(format!(
+                                "<@{}>, your scheduled ping #{} could not be sent:{}",
+                                scheduled.user_id,
+                                scheduled.id,
+                                Handler::compose_ping_rejection(invalid_lists)
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if let Some(notice) = notice {
+                scheduled.channel_id.say(&ctx.http, notice).await.ok();
+            }
+
+            let data = ctx.data.read().await;
             let BotData { database: db, .. } = data
-                .get_mut::<DB>()
+                .get::<DB>()
                 .expect("Could not find database in bot data");
-
-            let mut embed = CreateEmbed::default();
             if let Ok(mut x) = db.clone().lock() {
-                if x.accept_proposal(list_id) {
-                    embed = embed.title("Proposal acccepted");
-                } else {
-                    if x.get_list_exists(list_id) {
-                        embed = embed.title("Proposal already accepted");
-                    } else {
-                        embed = embed.title("Proposal already removed");
+                if let Some(schedule) = scheduled.schedule.as_ref().filter(|_| !cancel) {
+                    // Missed occurrences are skipped, schedules that no longer parse are dropped.
+                    match Recurrence::parse(schedule)
+                        .ok()
+                        .and_then(|recurrence| recurrence.next_after(now))
+                    {
+                        Some(next_time) => x
+                            .reschedule_ping(scheduled.id, next_time)
+                            .expect("Failed to reschedule ping"),
+                        None => {
+                            x.remove_scheduled_ping(scheduled.id)
+                                .expect("Failed to remove scheduled ping");
+                        }
                     }
+                } else if cancel || scheduled.interval == 0 {
+                    x.remove_scheduled_ping(scheduled.id)
+                        .expect("Failed to remove scheduled ping");
+                } else {
+                    // Skip any occurrences that were missed while the bot was offline.
+                    let missed = (now - scheduled.next_time) / scheduled.interval;
+                    x.reschedule_ping(
+                        scheduled.id,
+                        scheduled.next_time + (missed + 1) * scheduled.interval,
+                    )
+                    .expect("Failed to reschedule ping");
                 }
             }
-            channel_id
-                .edit_message(
-                    &ctx.http,
-                    message_id,
-                    EditMessage::new().embed(embed).components(vec![]),
-                )
-                .await
-                .unwrap();
         }
-        Handler::send_text(
-            "Check the original proposal message for results.",
-            command,
-            ctx,
-            true,
-        )
-        .await;
+    }
+
+    /// Treats everyone on a list who is missing from a complete roster as having left now, so
+    /// members who left while the bot was offline are pruned after the grace period as well.
+    fn reconcile_departures(db: &Mutex<Database>, guild_id: GuildId, present: &BTreeSet<UserId>) {
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        if let Ok(mut x) = db.lock() {
+            let recorded = x
+                .reconcile_departures(guild_id, present, now)
+                .expect("Failed to reconcile departures");
+            if recorded > 0 {
+                println!(
+                    "Recorded {} departures of members missing from guild {}",
+                    recorded, guild_id
+                );
+            }
+        }
     }
 
     /// Gets called without guild context automatically
-    async fn external_check_proposals(ctx: &Context) {
-        let mut data = ctx.data.write().await;
+    async fn external_prune_departures(ctx: &Context) {
+        let data = ctx.data.read().await;
         let BotData { database: db, .. } = data
-            .get_mut::<DB>()
+            .get::<DB>()
             .expect("Could not find database in bot data");
         let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
 
-        let mut replies: Vec<(ChannelId, MessageId, bool)> = vec![];
         if let Ok(mut x) = db.clone().lock() {
-            for (guild_id, proposal) in x.get_bot_proposals() {
-                let ProposalStatus::ACTIVE(list_id, votes, timestamp, channel_id, message_id) =
-                    proposal
-                else {
-                    continue;
-                };
-                let (_, vote_timeout, vote_threshold) = x.get_propose_settings(guild_id);
-                if votes >= vote_threshold {
-                    x.accept_proposal(list_id);
-                    if channel_id != 0 && message_id != 0 {
-                        replies.push((channel_id, message_id, true));
-                    }
-                } else if timestamp + vote_timeout <= now {
-                    x.remove_proposal(list_id).unwrap();
-                    x.remove_list(list_id).unwrap();
-                    if channel_id != 0 && message_id != 0 {
-                        replies.push((channel_id, message_id, false));
-                    }
-                }
-                // We'd rather wait a little longer than reach a rate limit.
-                if replies.len() >= 10 {
-                    break;
+            let removed = x
+                .prune_departed_members(now)
+                .expect("Failed to prune departed members");
+            if removed > 0 {
+                println!("Removed {} memberships of departed members", removed);
+            }
+        }
+    }
+
+    async fn external_purge_archives(ctx: &Context) {
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+
+        if let Ok(mut x) = db.lock() {
+            let purged = x
+                .purge_archived_lists(now)
+                .expect("Failed to purge archived lists");
+            if purged > 0 {
+                println!("Deleted {} archived lists", purged);
+            }
+        };
+    }
+
+    async fn external_expire_join_requests(ctx: &Context) {
+        let data = ctx.data.read().await;
+        let BotData { database: db, .. } = data
+            .get::<DB>()
+            .expect("Could not find database in bot data");
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+
+        if let Ok(mut x) = db.lock() {
+            let expired = x
+                .purge_join_requests(now)
+                .expect("Failed to purge join requests");
+            if expired > 0 {
+                println!("Dropped {} join requests nobody decided on", expired);
+            }
+        };
+    }
+
+    /// Forgets pings and splits nobody acted on in time, their forms, buttons or menus were most
+    /// likely dismissed.
+    async fn external_evict_pending(ctx: &Context) {
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        let mut data = ctx.data.write().await;
+        let BotData {
+            pending_pings,
+            pending_splits,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        pending_pings.retain(|_, (_, created)| *created + PENDING_TIMEOUT > now);
+        pending_splits.retain(|_, (_, _, created)| *created + PENDING_TIMEOUT > now);
+    }
+
+    /// Ends temporary memberships that ran out and, when the guild wants that, tells the members
+    /// with a button to renew them.
+    async fn external_expire_memberships(ctx: &Context) {
+        let now = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        let mut expired = vec![];
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                for (guild_id, list_id, member_id, duration) in x
+                    .remove_expired_memberships(now)
+                    .expect("Failed to remove expired memberships")
+                {
+                    let list_name = x
+                        .get_list_names(list_id)
+                        .first()
+                        .cloned()
+                        .unwrap_or_default();
+                    expired.push((
+                        guild_id,
+                        list_id,
+                        list_name,
+                        member_id,
+                        duration,
+                        x.get_list_role(list_id),
+                        x.get_expiry_notice(guild_id),
+                    ));
                 }
+            };
+        }
+
+        for (guild_id, list_id, list_name, member_id, duration, role, notice) in expired {
+            if let Some(role_id) = role {
+                ctx.http
+                    .remove_member_role(guild_id, member_id, role_id, Some("Membership expired"))
+                    .await
+                    .ok();
+            }
+            if !notice {
+                continue;
             }
+            let guild_name = guild_id.name(&ctx.cache).unwrap_or_default();
+            member_id
+                .direct_message(
+                    &ctx.http,
+                    CreateMessage::new()
+                        .content(format!(
+                            "Your membership of the list {} in {} has ended.",
+                            list_name, guild_name
+                        ))
+                        .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                            format!("membership_renew:{}:{}", list_id, duration),
+                        )
+                        .label("Renew")
+                        .style(ButtonStyle::Primary)])]),
+                )
+                .await
+                .ok();
         }
+    }
 
-        let mut awaits = vec![];
-        let mut more_awaits = vec![];
-        for (channel_id, message_id, accepted) in replies {
-            let reference =
-                serenity::model::prelude::MessageReference::from((channel_id, message_id));
-            let new_message = if accepted {
-                "Proposal accepted"
-            } else {
-                "Proposal timed out"
+    async fn renew_membership_from_component(
+        &self,
+        component: &ComponentInteraction,
+        ctx: &Context,
+    ) {
+        let mut fields = component
+            .data
+            .custom_id
+            .split(':')
+            .skip(1)
+            .map(|field| field.parse::<u64>().unwrap_or(0));
+        let (Some(list_id), Some(duration)) = (fields.next(), fields.next()) else {
+            return;
+        };
+
+        let mut list = None;
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                if x.get_list_exists(list_id) && !x.is_archived(list_id) {
+                    list = Some((
+                        x.get_list_guild(list_id).expect("List without guild"),
+                        x.get_list_names(list_id)
+                            .first()
+                            .cloned()
+                            .unwrap_or_default(),
+                    ));
+                }
             };
-            awaits.push(Handler::send_channel(
-                new_message,
-                channel_id,
-                ctx,
-                false,
-                Some(reference),
-            ));
-            let mut embed = CreateEmbed::default();
+        }
 
-            embed = embed.description(new_message);
-            more_awaits.push(channel_id.edit_message(
-                &ctx.http,
-                message_id,
-                EditMessage::new().embed(embed),
-            ));
+        // The button lives in direct messages, so it can be used after leaving the server
+        let mut present = true;
+        if let Some((guild_id, _)) = list {
+            let data = ctx.data.read().await;
+            let BotData { rosters, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            present = match rosters.get(&guild_id) {
+                Some(roster) if roster.complete => roster.members.contains(&component.user.id),
+                _ => guild_id.member(&ctx.http, component.user.id).await.is_ok(),
+            };
         }
-        join_all(awaits).await;
-        join_all(more_awaits).await;
+
+        let duration = Some(duration).filter(|duration| *duration > 0);
+        let content = match list {
+            None => "This list no longer exists.".to_string(),
+            Some(_) if !present => {
+                "You are no longer a member of this server, the membership was not renewed."
+                    .to_string()
+            }
+            Some((guild_id, list_name)) => match self
+                .add_member(
+                    guild_id,
+                    &list_name,
+                    component.user.id,
+                    JoinAuthority::Member,
+                    duration,
+                    ctx,
+                )
+                .await
+            {
+                JoinResult::Succes => format!(
+                    "Renewed your membership of the list {}{}.",
+                    list_name,
+                    Handler::describe_expiry(duration)
+                ),
+                JoinResult::AlreadyMember => {
+                    format!("You are already on the list {}.", list_name)
+                }
+                JoinResult::ApprovalRequested | JoinResult::ApprovalPending => format!(
+                    "The list {} needs approval, you will get a message once your request is handled.",
+                    list_name
+                ),
+                _ => format!(
+                    "Could not renew your membership of the list {}.",
+                    list_name
+                ),
+            },
+        };
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("{}\n{}", component.message.content, content))
+                        .components(vec![]),
+                ),
+            )
+            .await
+            .ok();
     }
 
     async fn handle_list_proposals(&self, command: &CommandInteraction, ctx: &Context) {
@@ -2216,59 +6493,6 @@ impl Handler {
         responses
     }
 
-    async fn _handle_context_ping(&self, command: &CommandInteraction, ctx: &Context) {
-        let main_question = CreateActionRow::InputText(
-            CreateInputText::new(
-                InputTextStyle::Paragraph,
-                "Name something you like within Inverted Fate?",
-                "top",
-            )
-            .value("Doesn't have to be your favorite thing, but try to be somewhat specific"),
-        );
-        let cominter = CreateActionRow::InputText(
-            CreateInputText::new(
-                InputTextStyle::Paragraph,
-                "What do you expect from the community?",
-                "main",
-            )
-            .placeholder("Mostly anything is fine, don't worry!"),
-        );
-        let friends = CreateActionRow::InputText(CreateInputText::new(
-            InputTextStyle::Short,
-            "If a friend referred you, please mention them",
-            "side",
-        ));
-
-        let cc = CreateActionRow::InputText(
-            CreateInputText::new(
-                InputTextStyle::Paragraph,
-                "Are you a content creator?",
-                "bottom",
-            )
-            .placeholder("Feel free to share links where applicable."),
-        );
-
-        let other_fandoms = CreateActionRow::InputText(
-            CreateInputText::new(
-                InputTextStyle::Paragraph,
-                "What other interests do you have?",
-                "bottomer",
-            )
-            .value("Fandoms, games, hobbies etc. outside of undertale."),
-        );
-
-        command
-            .create_response(
-                &ctx.http,
-                CreateInteractionResponse::Modal(
-                    CreateModal::new("AAA", "Welcome to the Inverted Fate community.")
-                        .components(vec![main_question, cominter, friends, cc, other_fandoms]),
-                ),
-            )
-            .await
-            .unwrap();
-    }
-
     async fn handle_log_purge(&self, command: &CommandInteraction, ctx: &Context) {
         let Some(guild_id) = command.guild_id else {
             Handler::send_not_in_guild(command, ctx).await;
@@ -2650,20 +6874,34 @@ impl EventHandler for Handler {
         if let Interaction::Command(command) = interaction {
             match command.data.name.as_str() {
                 "ping" => self.handle_ping(&command, &ctx).await,
-                // "ping with context" => self.handle_context_ping(&command, &ctx).await,
+                "Ping list" => self.handle_context_ping(&command, &ctx).await,
                 "join" => self.handle_join(&command, &ctx).await,
                 "leave" => self.handle_leave(&command, &ctx).await,
                 "get" => self.handle_get(&command, &ctx).await,
                 "list" => self.handle_list(&command, &ctx).await,
                 "propose" => self.handle_propose(&command, &ctx).await,
                 "list_proposals" => self.handle_list_proposals(&command, &ctx).await,
+                "schedule_ping" => self.handle_schedule_ping(&command, &ctx).await,
+                "dm_pings" => self.handle_dm_pings(&command, &ctx).await,
+                "list_scheduled" => self.handle_list_scheduled(&command, &ctx).await,
+                "cancel_scheduled" => self.handle_cancel_scheduled(&command, &ctx).await,
                 // admin commands
+                "ping_history" => self.handle_ping_history(&command, &ctx).await,
                 "alias" => self.handle_alias(&command, &ctx).await,
                 "create" => self.handle_create(&command, &ctx).await,
                 "remove" => self.handle_remove(&command, &ctx).await,
+                "restore" => self.handle_restore(&command, &ctx).await,
+                "rename" => self.handle_rename(&command, &ctx).await,
+                "merge" => self.handle_merge(&command, &ctx).await,
+                "split" => self.handle_split(&command, &ctx).await,
+                "info" => self.handle_info(&command, &ctx).await,
                 "add" => self.handle_add(&command, &ctx).await,
                 "kick" => self.handle_kick(&command, &ctx).await,
                 "remove_alias" => self.handle_remove_alias(&command, &ctx).await,
+                "link_role" => self.handle_link_role(&command, &ctx).await,
+                "list_managers" => self.handle_list_managers(&command, &ctx).await,
+                "category" => self.handle_category(&command, &ctx).await,
+                "include" => self.handle_include(&command, &ctx).await,
                 "configure" => self.handle_configure(&command, &ctx).await,
                 "Cancel proposal" => self.handle_cancel_proposal(&command, &ctx).await,
                 "Accept proposal" => self.handle_accept_proposal(&command, &ctx).await,
@@ -2679,10 +6917,16 @@ impl EventHandler for Handler {
             };
         } else if let Interaction::Autocomplete(completable) = interaction {
             match completable.data.name.as_str() {
-                "ping" | "remove" => self.autocomplete_ping(&completable, &ctx).await,
+                "ping" | "remove" | "ping_history" | "schedule_ping" | "link_role"
+                | "list_managers" | "include" | "merge" | "split" | "info" => {
+                    self.autocomplete_ping(&completable, &ctx).await
+                }
                 "configure" => self.autocomplete_configure(&completable, &ctx).await,
+                "category" | "list" => self.autocomplete_category(&completable, &ctx).await,
+                "restore" => self.autocomplete_restore(&completable, &ctx).await,
                 "alias" => self.autocomplete_alias(&completable, &ctx).await,
                 "remove_alias" => self.autocomplete_alias(&completable, &ctx).await,
+                "rename" => self.autocomplete_alias(&completable, &ctx).await,
                 "add" | "join" => self.autocomplete_join(&completable, &ctx).await,
                 "kick" | "leave" => self.autocomplete_leave(&completable, &ctx).await,
                 _ => (),
@@ -2690,27 +6934,55 @@ impl EventHandler for Handler {
         } else if let Interaction::Component(component) = interaction {
             match component
                 .message
-                .interaction // we may get modals not spawned by interactions in the future, if so this may fail
+                .interaction // messages sent in response to modals may not carry this
                 .as_ref()
-                .unwrap()
-                .name
-                .as_str()
+                .map_or("", |interaction| interaction.name.as_str())
             {
                 "list" => self.list_page_from_component(&component, &ctx).await,
                 "propose" => self.propose_vote_from_component(&component, &ctx).await,
                 "log_purge" => self.process_log_purge(&component, &ctx).await,
-                _ => println!("Unknown interaction: {:?}", &component), // remove eventually?
+                "ping_history" => {
+                    self.ping_history_page_from_component(&component, &ctx)
+                        .await
+                }
+                _ => match component.data.custom_id.split(':').next().unwrap_or("") {
+                    "ping_context" => self.context_ping_from_component(&component, &ctx).await,
+                    "ping_queue" => self.queue_ping_from_component(&component, &ctx).await,
+                    "ping_confirm" | "ping_cancel" => {
+                        self.confirm_ping_from_component(&component, &ctx).await
+                    }
+                    "join_approve" | "join_deny" => {
+                        self.join_request_from_component(&component, &ctx).await
+                    }
+                    "membership_renew" => {
+                        self.renew_membership_from_component(&component, &ctx).await
+                    }
+                    "list_restore" => self.restore_list_from_component(&component, &ctx).await,
+                    "list_split" => self.split_list_from_component(&component, &ctx).await,
+                    "list_join" | "list_leave" => {
+                        self.join_leave_from_component(&component, &ctx).await
+                    }
+                    "get_page" => self.get_page_from_component(&component, &ctx).await,
+                    "get_leave" => self.get_leave_from_component(&component, &ctx).await,
+                    _ => println!("Unknown interaction: {:?}", &component), // remove eventually?
+                },
             }
         } else if let Interaction::Modal(modal) = interaction {
-            modal
-                .create_response(
-                    &ctx.http,
-                    CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::new().content("Succes"),
-                    ),
-                )
-                .await
-                .unwrap();
+            match modal.data.custom_id.split(':').next().unwrap_or("") {
+                "ping_context" => self.context_ping_from_modal(&modal, &ctx).await,
+                "ping_message" => self.ping_message_from_modal(&modal, &ctx).await,
+                _ => {
+                    modal
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new().content("Succes"),
+                            ),
+                        )
+                        .await
+                        .unwrap();
+                }
+            }
         }
     }
 
@@ -2721,6 +6993,18 @@ impl EventHandler for Handler {
         _: Option<Member>,
         changes: serenity::all::GuildMemberUpdateEvent,
     ) {
+        // Role-backed lists follow the member's current roles, whether or not the old
+        // member was cached
+        {
+            let data = ctx.data.read().await;
+            let BotData { database: db, .. } = data
+                .get::<DB>()
+                .expect("Could not find database in bot data");
+            if let Ok(mut x) = db.lock() {
+                x.sync_role_memberships(changes.guild_id, changes.user.id, &changes.roles);
+            };
+        }
+
         if let Some(old) = old_if_available {
             let oldset = BTreeSet::from_iter(old.roles.iter().cloned());
             let newset = BTreeSet::from_iter(changes.roles.iter().cloned());
@@ -2765,6 +7049,27 @@ impl EventHandler for Handler {
     }
 
     async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        {
+            let mut data = ctx.data.write().await;
+            let BotData {
+                database: db,
+                rosters,
+                ..
+            } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            rosters
+                .entry(new_member.guild_id)
+                .or_default()
+                .members
+                .insert(new_member.user.id);
+            // Rejoining within the grace period keeps all list memberships.
+            if let Ok(mut x) = db.clone().lock() {
+                x.remove_departure(new_member.guild_id, new_member.user.id)
+                    .expect("Failed to clear departure");
+            }
+        }
+
         for (channel, message_str) in self
             .check_triggers(
                 &ctx,
@@ -2781,6 +7086,72 @@ impl EventHandler for Handler {
         }
     }
 
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        _member_data_if_available: Option<Member>,
+    ) {
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            rosters,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        if let Some(roster) = rosters.get_mut(&guild_id) {
+            roster.members.remove(&user.id);
+        }
+        let timestamp = serenity::model::Timestamp::now().unix_timestamp() as u64;
+        if let Ok(mut x) = db.clone().lock() {
+            x.add_departure(guild_id, user.id, timestamp)
+                .expect("Failed to store departure");
+        }
+    }
+
+    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: Option<bool>) {
+        // Small guilds arrive with all their members, larger ones need to be chunked.
+        let complete = guild.members.len() as u64 >= guild.member_count;
+        {
+            let mut data = ctx.data.write().await;
+            let BotData {
+                database: db,
+                rosters,
+                ..
+            } = data
+                .get_mut::<DB>()
+                .expect("Could not find database in bot data");
+            let members: BTreeSet<UserId> = guild.members.keys().cloned().collect();
+            if complete {
+                Handler::reconcile_departures(db, guild.id, &members);
+            }
+            rosters.insert(guild.id, GuildRoster { members, complete });
+        }
+        if !complete {
+            ctx.shard
+                .chunk_guild(guild.id, None, false, ChunkGuildFilter::None, None);
+        }
+    }
+
+    async fn guild_members_chunk(&self, ctx: Context, chunk: GuildMembersChunkEvent) {
+        let mut data = ctx.data.write().await;
+        let BotData {
+            database: db,
+            rosters,
+            ..
+        } = data
+            .get_mut::<DB>()
+            .expect("Could not find database in bot data");
+        let roster = rosters.entry(chunk.guild_id).or_default();
+        roster.members.extend(chunk.members.keys());
+        if chunk.chunk_index + 1 == chunk.chunk_count {
+            roster.complete = true;
+            Handler::reconcile_departures(db, chunk.guild_id, &roster.members);
+        }
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
 
@@ -2817,6 +7188,12 @@ impl EventHandler for Handler {
             tokio::spawn(async move {
                 loop {
                     Handler::external_check_proposals(&ctx1).await;
+                    Handler::external_prune_departures(&ctx1).await;
+                    Handler::external_purge_archives(&ctx1).await;
+                    Handler::external_expire_memberships(&ctx1).await;
+                    Handler::external_run_scheduled_pings(&ctx1).await;
+                    Handler::external_evict_pending(&ctx1).await;
+                    Handler::external_expire_join_requests(&ctx1).await;
                     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
                 }
             });
@@ -2903,8 +7280,10 @@ async fn main() {
         let mut data = client.data.write().await;
         let bot_data = BotData {
             database: Arc::new(database),
-            global: std::collections::HashMap::new(),
-            local: std::collections::HashMap::new(),
+            rosters: HashMap::new(),
+            pending_pings: HashMap::new(),
+            pending_splits: HashMap::new(),
+            alias_indexes: HashMap::new(),
         };
         data.insert::<DB>(bot_data);
     }
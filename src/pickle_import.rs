@@ -299,7 +299,10 @@ pub fn import_pickled(ipath: &str, gid: GuildId, database: &mut Database) {
         println!("{}: {:?}", list_name, content_val[0]);
         match &content_val[..] {
             [Value::Dict(role_data), Value::Set(members)] => {
-                let list_id = db.add_list(guild_id, list_name).unwrap();
+                let Some(list_id) = db.add_list(guild_id, list_name) else {
+                    println!("Skipped {}, another list already uses this name", list_name);
+                    return;
+                };
                 let mut noping = false;
                 let mut nojoin = false;
                 for (key_val, item_val) in role_data {
@@ -312,7 +315,7 @@ pub fn import_pickled(ipath: &str, gid: GuildId, database: &mut Database) {
                                 noping = *b;
                             }
                             ("pingdelay", Value::I64(i)) => {
-                                db.set_cooldown(list_id, *i);
+                                db.set_cooldown(list_id, *i).unwrap();
                             }
                             ("description", Value::String(s)) => {
                                 db.set_description(list_id, &s);
@@ -0,0 +1,311 @@
+use serenity::model::id::UserId;
+use std::collections::{BTreeSet, HashMap};
+
+/// A set expression over list names, as accepted by /ping.
+///
+/// `&` (intersection) binds tighter than `|` (union) and `-` (difference), which are
+/// evaluated left to right, so `a & (b | c) - d` reads as `(a & (b | c)) - d`.
+/// A `-` only acts as an operator at the start of a word, so names like `mc-players`
+/// keep working; names containing other operator characters can be written in quotes.
+pub enum PingQuery {
+    List(String),
+    Union(Box<PingQuery>, Box<PingQuery>),
+    Intersection(Box<PingQuery>, Box<PingQuery>),
+    Difference(Box<PingQuery>, Box<PingQuery>),
+}
+
+#[derive(PartialEq)]
+enum Token {
+    Name(String),
+    And,
+    Or,
+    Minus,
+    Open,
+    Close,
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '&' | '|' | '(' | ')')
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut name = String::new();
+    let mut word_start = true;
+    let mut chars = text.chars();
+
+    fn push_name(tokens: &mut Vec<Token>, name: &mut String) {
+        let trimmed = name.trim();
+        if !trimmed.is_empty() {
+            tokens.push(Token::Name(trimmed.to_string()));
+        }
+        name.clear();
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                push_name(&mut tokens, &mut name);
+                let quoted: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                if quoted.trim().is_empty() {
+                    return Err("empty quoted list name".to_string());
+                }
+                tokens.push(Token::Name(quoted.trim().to_string()));
+                word_start = true;
+            }
+            '-' if word_start => {
+                push_name(&mut tokens, &mut name);
+                tokens.push(Token::Minus);
+            }
+            c if is_operator(c) => {
+                push_name(&mut tokens, &mut name);
+                tokens.push(match c {
+                    '&' => Token::And,
+                    '|' => Token::Or,
+                    '(' => Token::Open,
+                    _ => Token::Close,
+                });
+                word_start = true;
+            }
+            c if c.is_whitespace() => {
+                if !name.is_empty() && !name.ends_with(' ') {
+                    name.push(' ');
+                }
+                word_start = true;
+            }
+            c => {
+                name.push(c);
+                word_start = false;
+            }
+        }
+    }
+    push_name(&mut tokens, &mut name);
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn expression(&mut self) -> Result<PingQuery, String> {
+        let mut left = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Or) => {
+                    self.position += 1;
+                    left = PingQuery::Union(Box::new(left), Box::new(self.term()?));
+                }
+                Some(Token::Minus) => {
+                    self.position += 1;
+                    left = PingQuery::Difference(Box::new(left), Box::new(self.term()?));
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<PingQuery, String> {
+        let mut left = self.atom()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            left = PingQuery::Intersection(Box::new(left), Box::new(self.atom()?));
+        }
+        Ok(left)
+    }
+
+    fn atom(&mut self) -> Result<PingQuery, String> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        match token {
+            Some(Token::Name(name)) => Ok(PingQuery::List(name.clone())),
+            Some(Token::Open) => {
+                let inner = self.expression()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("missing closing parenthesis".to_string());
+                }
+                self.position += 1;
+                Ok(inner)
+            }
+            Some(Token::Close) => Err("unexpected closing parenthesis".to_string()),
+            Some(_) => Err("expected a list name before an operator".to_string()),
+            None => Err("expected a list name at the end".to_string()),
+        }
+    }
+}
+
+impl PingQuery {
+    pub fn parse(text: &str) -> Result<PingQuery, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err("no lists given".to_string());
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let query = parser.expression()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(Token::Close) => Err("unexpected closing parenthesis".to_string()),
+            Some(_) => Err("missing operator between list names".to_string()),
+        }
+    }
+
+    /// All list names referenced by this query, in order of appearance.
+    pub fn list_names(&self) -> Vec<&str> {
+        match self {
+            PingQuery::List(name) => vec![name.as_str()],
+            PingQuery::Union(a, b)
+            | PingQuery::Intersection(a, b)
+            | PingQuery::Difference(a, b) => {
+                let mut names = a.list_names();
+                names.extend(b.list_names());
+                names
+            }
+        }
+    }
+
+    /// Evaluates the query, every referenced list must be present in `lists`.
+    pub fn evaluate(&self, lists: &HashMap<String, BTreeSet<UserId>>) -> BTreeSet<UserId> {
+        match self {
+            PingQuery::List(name) => lists.get(name).cloned().unwrap_or_default(),
+            PingQuery::Union(a, b) => &a.evaluate(lists) | &b.evaluate(lists),
+            PingQuery::Intersection(a, b) => &a.evaluate(lists) & &b.evaluate(lists),
+            PingQuery::Difference(a, b) => &a.evaluate(lists) - &b.evaluate(lists),
+        }
+    }
+}
+
+/// Splits a partially typed query into everything before the list name currently being
+/// typed and that name, for use in autocomplete. An opening quote of the name stays in the
+/// first part.
+pub fn split_last_term(text: &str) -> (&str, &str) {
+    let mut split = 0;
+    let mut word_start = true;
+    let mut quoted = false;
+    for (index, c) in text.char_indices() {
+        if c == '"' {
+            split = index + c.len_utf8();
+            quoted = !quoted;
+            word_start = true;
+        } else if quoted {
+            continue;
+        } else if is_operator(c) || (c == '-' && word_start) {
+            split = index + c.len_utf8();
+            word_start = true;
+        } else {
+            word_start = c.is_whitespace();
+        }
+    }
+    let fragment = &text[split..];
+    let split = split + (fragment.len() - fragment.trim_start().len());
+    (&text[..split], &text[split..])
+}
+
+/// Appends a list name to the first part returned by `split_last_term`, quoting it where
+/// it would otherwise not read back as a single name.
+pub fn complete_term(prefix: &str, name: &str) -> String {
+    if prefix.matches('"').count() % 2 == 1 {
+        format!("{}{}\"", prefix, name)
+    } else if name.chars().any(is_operator) || name.split_whitespace().any(|w| w.starts_with('-')) {
+        format!("{}\"{}\"", prefix, name)
+    } else {
+        format!("{}{}", prefix, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(query: &PingQuery) -> String {
+        match query {
+            PingQuery::List(name) => name.clone(),
+            PingQuery::Union(a, b) => format!("({} | {})", render(a), render(b)),
+            PingQuery::Intersection(a, b) => format!("({} & {})", render(a), render(b)),
+            PingQuery::Difference(a, b) => format!("({} - {})", render(a), render(b)),
+        }
+    }
+
+    fn parse(text: &str) -> String {
+        render(&PingQuery::parse(text).unwrap())
+    }
+
+    #[test]
+    fn intersection_binds_tighter() {
+        assert_eq!(parse("a | b & c"), "(a | (b & c))");
+        assert_eq!(parse("a & b - c"), "((a & b) - c)");
+        assert_eq!(parse("a - b | c"), "((a - b) | c)");
+        assert_eq!(parse("a & (b | c) - d"), "((a & (b | c)) - d)");
+    }
+
+    #[test]
+    fn names_keep_hyphens_and_spaces() {
+        assert_eq!(parse("mc-players - afk"), "(mc-players - afk)");
+        assert_eq!(parse("  board   games  "), "board games");
+        assert_eq!(parse("\"a & b\" | c"), "(a & b | c)");
+    }
+
+    #[test]
+    fn malformed_queries_are_rejected() {
+        for text in [
+            "", "a |", "& a", "(a | b", "a | b)", "a (b)", "\"\" | a", "a | ()",
+        ] {
+            assert!(PingQuery::parse(text).is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn list_names_in_order() {
+        let query = PingQuery::parse("c & (a | b) - c").unwrap();
+        assert_eq!(query.list_names(), vec!["c", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn evaluate_sets() {
+        let users = |ids: &[u64]| {
+            ids.iter()
+                .map(|id| UserId::new(*id))
+                .collect::<BTreeSet<_>>()
+        };
+        let lists = HashMap::from([
+            ("a".to_string(), users(&[1, 2, 3])),
+            ("b".to_string(), users(&[2, 3, 4])),
+            ("c".to_string(), users(&[3, 5])),
+        ]);
+        let evaluate = |text: &str| PingQuery::parse(text).unwrap().evaluate(&lists);
+        assert_eq!(evaluate("a | b"), users(&[1, 2, 3, 4]));
+        assert_eq!(evaluate("a & b"), users(&[2, 3]));
+        assert_eq!(evaluate("a - b"), users(&[1]));
+        assert_eq!(evaluate("a | b & c"), users(&[1, 2, 3]));
+        assert_eq!(evaluate("(a | b) & c"), users(&[3]));
+        assert_eq!(evaluate("a - b - c"), users(&[1]));
+        assert_eq!(evaluate("a - (b - c)"), users(&[1, 3]));
+    }
+
+    #[test]
+    fn split_at_the_name_being_typed() {
+        assert_eq!(split_last_term("mc-pl"), ("", "mc-pl"));
+        assert_eq!(split_last_term("a & (b | ga"), ("a & (b | ", "ga"));
+        assert_eq!(split_last_term("a - b"), ("a - ", "b"));
+        assert_eq!(split_last_term("a | \"x & y"), ("a | \"", "x & y"));
+        assert_eq!(split_last_term("\"x & y\" | b"), ("\"x & y\" | ", "b"));
+    }
+
+    #[test]
+    fn completed_names_read_back() {
+        assert_eq!(complete_term("a | ", "games"), "a | games");
+        assert_eq!(complete_term("a | \"", "x & y"), "a | \"x & y\"");
+        assert_eq!(complete_term("a | ", "x & y"), "a | \"x & y\"");
+        assert_eq!(complete_term("", "-minus"), "\"-minus\"");
+        assert_eq!(complete_term("", "mc-players"), "mc-players");
+        let query = PingQuery::parse(&complete_term("a | ", "x (old)")).unwrap();
+        assert_eq!(query.list_names(), vec!["a", "x (old)"]);
+    }
+}
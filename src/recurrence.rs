@@ -0,0 +1,256 @@
+/// A cron-like schedule in UTC, as accepted by /schedule_ping.
+///
+/// The five fields are the minute, hour, day of the month, month and day of the week. Each field
+/// is `*`, a number, a range `a-b` or a comma separated list of those, and can be followed by a
+/// step such as `*/15`. Months and weekdays may also be written by name, as in `0 18 * * fri`,
+/// and both 0 and 7 mean Sunday. Like cron, when both the day of the month and the day of the
+/// week are restricted, a day matches if either of them does.
+pub struct Recurrence {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "sunday",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+];
+
+/// How far ahead to look for the next occurrence, long enough to reach the next leap day.
+const SEARCH_DAYS: u64 = 366 * 8;
+
+/// Reads a single value of a field, either a number or a name of at least three letters.
+fn parse_value(text: &str, field: &str, names: &[&str], offset: u64) -> Result<u64, String> {
+    if let Ok(value) = text.parse::<u64>() {
+        return Ok(value);
+    }
+    let word = text.to_lowercase();
+    names
+        .iter()
+        .position(|name| word.len() >= 3 && name.starts_with(&word))
+        .map(|position| position as u64 + offset)
+        .ok_or_else(|| format!("\"{}\" is not a valid {}", text, field))
+}
+
+/// Reads one field into a bit set of the values it allows.
+fn parse_field(
+    text: &str,
+    field: &str,
+    min: u64,
+    max: u64,
+    names: &[&str],
+    offset: u64,
+) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u64>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => return Err(format!("\"{}\" is not a valid step", step)),
+            },
+            None => (part, None),
+        };
+        let (first, last) = if range == "*" {
+            (min, max)
+        } else if let Some((first, last)) = range.split_once('-') {
+            (
+                parse_value(first, field, names, offset)?,
+                parse_value(last, field, names, offset)?,
+            )
+        } else {
+            let value = parse_value(range, field, names, offset)?;
+            // `5/10` is short for `5-max/10`
+            (value, if step.is_some() { max } else { value })
+        };
+        if first < min || last > max {
+            return Err(format!(
+                "the {} {} is out of range",
+                field,
+                if first < min { first } else { last }
+            ));
+        }
+        if first > last {
+            return Err(format!("the {} range {} is backwards", field, range));
+        }
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// The month and day of the month of a number of days since 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u64) {
+    let days = days + 719468;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (month, day)
+}
+
+impl Recurrence {
+    pub fn parse(text: &str) -> Result<Recurrence, String> {
+        let fields = text.split_whitespace().collect::<Vec<&str>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(
+                "a schedule has five fields: minute, hour, day, month and weekday".to_string(),
+            );
+        };
+        let weekdays_bits = parse_field(weekdays, "weekday", 0, 7, &WEEKDAYS, 0)?;
+        Ok(Recurrence {
+            minutes: parse_field(minutes, "minute", 0, 59, &[], 0)?,
+            hours: parse_field(hours, "hour", 0, 23, &[], 0)?,
+            days: parse_field(days, "day", 1, 31, &[], 0)?,
+            months: parse_field(months, "month", 1, 12, &MONTHS, 1)?,
+            // 7 is another way to write Sunday
+            weekdays: (weekdays_bits | weekdays_bits >> 7) & 0x7f,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+
+    fn matches_day(&self, days: u64) -> bool {
+        let (month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday
+        let weekday = (days + 4) % 7;
+        let day_matches = self.days & 1 << day != 0;
+        let weekday_matches = self.weekdays & 1 << weekday != 0;
+        self.months & 1 << month != 0
+            && match (self.any_day, self.any_weekday) {
+                (true, true) => true,
+                (false, true) => day_matches,
+                (true, false) => weekday_matches,
+                (false, false) => day_matches || weekday_matches,
+            }
+    }
+
+    /// The first time strictly after `time` this schedule fires, or None if it never does.
+    pub fn next_after(&self, time: u64) -> Option<u64> {
+        let start = time / 60 + 1;
+        let first_day = start / 1440;
+        (first_day..first_day + SEARCH_DAYS)
+            .filter(|days| self.matches_day(*days))
+            .find_map(|days| {
+                let from = if days == first_day { start % 1440 } else { 0 };
+                (from..1440)
+                    .find(|minute| {
+                        self.hours & 1 << (minute / 60) != 0
+                            && self.minutes & 1 << (minute % 60) != 0
+                    })
+                    .map(|minute| (days * 1440 + minute) * 60)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-16 00:00 UTC, a Friday
+    const FRIDAY: u64 = 1792108800;
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 86400;
+
+    fn next(schedule: &str, time: u64) -> Option<u64> {
+        Recurrence::parse(schedule).unwrap().next_after(time)
+    }
+
+    #[test]
+    fn every_minute_fires_on_the_next_minute() {
+        assert_eq!(next("* * * * *", FRIDAY), Some(FRIDAY + 60));
+        assert_eq!(next("* * * * *", FRIDAY + 59), Some(FRIDAY + 60));
+    }
+
+    #[test]
+    fn weekly_by_name_and_number() {
+        let friday_evening = FRIDAY + 18 * HOUR;
+        assert_eq!(next("0 18 * * fri", FRIDAY), Some(friday_evening));
+        assert_eq!(
+            next("0 18 * * Friday", friday_evening),
+            Some(friday_evening + 7 * DAY)
+        );
+        assert_eq!(next("0 18 * * 5", friday_evening - 1), Some(friday_evening));
+        assert_eq!(next("0 0 * * 7", FRIDAY), Some(FRIDAY + 2 * DAY));
+        assert_eq!(next("0 0 * * 0", FRIDAY), Some(FRIDAY + 2 * DAY));
+    }
+
+    #[test]
+    fn steps_ranges_and_lists() {
+        assert_eq!(next("*/15 * * * *", FRIDAY + 60), Some(FRIDAY + 15 * 60));
+        assert_eq!(
+            next("10/20 * * * *", FRIDAY + 11 * 60),
+            Some(FRIDAY + 30 * 60)
+        );
+        assert_eq!(
+            next("0 9-17/4 * * *", FRIDAY + 10 * HOUR),
+            Some(FRIDAY + 13 * HOUR)
+        );
+        assert_eq!(
+            next("0 6,20 * * mon-fri", FRIDAY + 21 * HOUR),
+            Some(FRIDAY + 3 * DAY + 6 * HOUR)
+        );
+    }
+
+    #[test]
+    fn day_and_weekday_match_either() {
+        // The 17th is the Saturday after, the Monday follows two days later
+        assert_eq!(next("0 0 17 * mon", FRIDAY), Some(FRIDAY + DAY));
+        assert_eq!(next("0 0 17 * mon", FRIDAY + DAY), Some(FRIDAY + 3 * DAY));
+    }
+
+    #[test]
+    fn rare_and_impossible_dates() {
+        // 2028-02-29 00:00 UTC
+        assert_eq!(next("0 0 29 feb *", FRIDAY), Some(1835395200));
+        assert_eq!(next("0 0 30 2 *", FRIDAY), None);
+        // 2027-01-01 00:00 UTC
+        assert_eq!(next("0 0 1 1 *", FRIDAY), Some(1798761600));
+    }
+
+    #[test]
+    fn rejects_malformed_schedules() {
+        assert!(Recurrence::parse("0 18 * *").is_err());
+        assert!(Recurrence::parse("0 18 * * * *").is_err());
+        assert!(Recurrence::parse("60 * * * *").is_err());
+        assert!(Recurrence::parse("* 24 * * *").is_err());
+        assert!(Recurrence::parse("* * 0 * *").is_err());
+        assert!(Recurrence::parse("* * * 13 *").is_err());
+        assert!(Recurrence::parse("* * * * 8").is_err());
+        assert!(Recurrence::parse("*/0 * * * *").is_err());
+        assert!(Recurrence::parse("5-1 * * * *").is_err());
+        assert!(Recurrence::parse("* * * * fr").is_err());
+        assert!(Recurrence::parse("* * * * someday").is_err());
+    }
+}
@@ -1,5 +1,6 @@
-use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
 use std::cmp::max;
+use std::collections::BTreeSet;
 use std::fmt;
 
 pub type ListId = u64;
@@ -13,6 +14,43 @@ pub struct PingList {
     pub join_permission: PERMISSION,
     pub ping_permission: PERMISSION,
     pub visible: bool,
+    pub category: Option<String>,
+}
+
+/// A ping as it was sent, kept so moderators can look back at who pinged what.
+pub struct PingRecord {
+    pub id: u64,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub user_id: UserId,
+    pub list_ids: Vec<ListId>,
+    pub member_count: usize,
+    pub timestamp: u64,
+    pub message_ids: Vec<MessageId>,
+}
+
+/// Restricts the ping history to a single list or user and a time range `[since, until)`.
+pub struct PingHistoryFilter {
+    pub list_id: Option<ListId>,
+    pub user_id: Option<UserId>,
+    pub since: u64,
+    pub until: u64,
+}
+
+/// A ping that fires at `next_time`, and every `interval` seconds after if that is not 0, or
+/// whenever its cron-like `schedule` says so if it has one.
+/// Queued pings were held back by a cooldown and are sent as soon as it runs out.
+pub struct ScheduledPing {
+    pub id: u64,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub user_id: UserId,
+    pub queries: Vec<String>,
+    pub note: String,
+    pub next_time: u64,
+    pub interval: u64,
+    pub schedule: Option<String>,
+    pub queued: bool,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -60,6 +98,57 @@ impl PERMISSION {
     }
 }
 
+/// How the members of a list are reached when it is pinged.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum DeliveryMode {
+    Channel = 0,
+    DirectMessage = 1,
+    Both = 2,
+}
+
+impl fmt::Display for DeliveryMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DeliveryMode::Channel => "Channel mentions",
+                DeliveryMode::DirectMessage => "Direct messages",
+                DeliveryMode::Both => "Channel mentions and direct messages",
+            }
+        )
+    }
+}
+
+impl DeliveryMode {
+    /// Pinging lists with different delivery modes at once reaches members in both ways.
+    pub fn combine(self, other: DeliveryMode) -> DeliveryMode {
+        if self == other {
+            self
+        } else {
+            DeliveryMode::Both
+        }
+    }
+
+    pub fn fromint(value: u64) -> DeliveryMode {
+        match value {
+            0 => DeliveryMode::Channel,
+            1 => DeliveryMode::DirectMessage,
+            2 => DeliveryMode::Both,
+            _ => panic!("Invalid delivery mode value"),
+        }
+    }
+
+    pub fn from_str(value: &str) -> Result<DeliveryMode, &str> {
+        match value {
+            "0" => Ok(DeliveryMode::Channel),
+            "1" => Ok(DeliveryMode::DirectMessage),
+            "2" => Ok(DeliveryMode::Both),
+            _ => Err("Invalid string"),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum LOGTRIGGER {
     RoleAdd(RoleId),
@@ -127,10 +216,35 @@ pub enum ProposalStatus {
     REMOVED,
 }
 
+/// Who adds a member to a list, which decides the checks that apply.
+#[derive(Clone, Copy, PartialEq)]
+pub enum JoinAuthority {
+    /// The member joins by themselves.
+    Member,
+    /// A manager of the list, who does not need approval but cannot hand out linked roles.
+    Manager,
+    /// A moderator, or an approved join request, skipping all checks.
+    Moderator,
+}
+
 pub enum JoinResult {
     Succes,
     AlreadyMember,
     ListDoesNotExist,
     BotError,
     MissingPerms,
+    RoleUnavailable,
+    ApprovalRequested,
+    ApprovalPending,
+    ApprovalUnavailable,
+    /// A recent request was denied, a new one can be made from this timestamp on.
+    ApprovalDenied(u64),
+}
+
+/// The members currently present in a guild, kept up to date from gateway events.
+/// Only trusted once `complete` is set, which happens after all chunks arrived.
+#[derive(Default)]
+pub struct GuildRoster {
+    pub members: BTreeSet<UserId>,
+    pub complete: bool,
 }